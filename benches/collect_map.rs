@@ -0,0 +1,21 @@
+#[macro_use]
+extern crate criterion;
+extern crate ron;
+
+use std::collections::HashMap;
+
+use criterion::Criterion;
+
+fn map() -> HashMap<String, Vec<u32>> {
+    (0..200).map(|i| (format!("key_{}", i), (0..i % 10).collect())).collect()
+}
+
+fn collect_map(c: &mut Criterion) {
+    let map = map();
+
+    c.bench_function("collect_map_unsorted", |b| b.iter(|| ron::ser::to_string(&map).unwrap()));
+    c.bench_function("collect_map_sorted", |b| b.iter(|| ron::ser::to_string_sorted(&map).unwrap()));
+}
+
+criterion_group!(benches, collect_map);
+criterion_main!(benches);
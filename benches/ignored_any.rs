@@ -0,0 +1,38 @@
+#[macro_use]
+extern crate criterion;
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use criterion::Criterion;
+
+use ron::value::Value;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Known {
+    name: String,
+    id: u32,
+}
+
+fn input() -> String {
+    let unknown: Vec<String> = (0..10)
+        .map(|i| format!(
+            "unknown_{}: (tags: [\"a\", \"b\", \"c\"], weights: {{1: 0.5, 2: 1.5}}, next: Some({})),",
+            i, i,
+        ))
+        .collect();
+
+    format!("Known(name: \"known\", {} id: 7)", unknown.concat())
+}
+
+fn ignored_any(c: &mut Criterion) {
+    let s = input();
+
+    c.bench_function("skip_unknown_fields", |b| b.iter(|| ron::de::from_str::<Known>(&s).unwrap()));
+    // What ignoring the fields costs when they are parsed into values
+    c.bench_function("parse_unknown_fields", |b| b.iter(|| ron::de::from_str::<Value>(&s).unwrap()));
+}
+
+criterion_group!(benches, ignored_any);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+#[macro_use]
+extern crate criterion;
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use std::collections::HashMap;
+
+use criterion::Criterion;
+
+#[derive(Serialize)]
+struct Player {
+    name: String,
+    position: (f32, f32, f32),
+    health: u32,
+    inventory: Vec<Item>,
+    flags: HashMap<String, bool>,
+}
+
+#[derive(Serialize)]
+struct Item {
+    name: String,
+    count: u16,
+    weight: f64,
+}
+
+fn player() -> Player {
+    Player {
+        name: "Ferris".to_string(),
+        position: (1.5, -2.0, 300.25),
+        health: 100,
+        inventory: (0..50)
+            .map(|i| Item { name: format!("item {}", i), count: i, weight: f64::from(i) * 0.3 })
+            .collect(),
+        flags: (0..20).map(|i| (format!("flag_{}", i), i % 3 == 0)).collect(),
+    }
+}
+
+fn to_bytes(c: &mut Criterion) {
+    let player = player();
+
+    c.bench_function("to_string_into_bytes", |b| {
+        b.iter(|| ron::ser::to_string(&player).unwrap().into_bytes())
+    });
+    c.bench_function("to_bytes", |b| b.iter(|| ron::ser::to_bytes(&player).unwrap()));
+}
+
+criterion_group!(benches, to_bytes);
+criterion_main!(benches);
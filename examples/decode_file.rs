@@ -8,6 +8,7 @@ use std::fs::File;
 use ron::de::from_reader;
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct Config {
     boolean: bool,
     float: f32,
@@ -17,6 +18,7 @@ struct Config {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct Nested {
     a: String,
     b: char,
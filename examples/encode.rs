@@ -50,5 +50,5 @@ fn main() {
     let pretty = PrettyConfig::default_with(|x|{x.separate_tuple_members=true});
     let s = to_string_pretty(&data, pretty).expect("Serialization failed");
 
-    file.write(s.as_bytes()).expect("Failed to write data to file");
+    file.write_all(s.as_bytes()).expect("Failed to write data to file");
 }
@@ -2,6 +2,7 @@ use std::error::Error as StdError;
 use std::fmt;
 use std::str::Utf8Error;
 use std::io;
+use std::num::{ParseFloatError, ParseIntError};
 use std::string::FromUtf8Error;
 
 use serde::de;
@@ -11,14 +12,14 @@ use parse::Position;
 /// Deserialization result.
 pub type Result<T> = ::std::result::Result<T, Error>;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
     IoError(String),
     Message(String),
     Parser(ParseError, Position),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ParseError {
     Eof,
     ExpectedArray,
@@ -42,7 +43,31 @@ pub enum ParseError {
     ExpectedStringEnd,
     ExpectedIdentifier,
 
+    /// A `/*` block comment without a matching `*/`
+    UnclosedBlockComment,
     InvalidEscape,
+    InvalidByteString,
+    IntegerOutOfBounds {
+        /// The integer literal, including its sign
+        value: String,
+        /// The name of the target type
+        ty: &'static str,
+        min: i128,
+        max: i128,
+    },
+    /// A number like `007`, see `DeserializerConfig::allow_leading_zeros`
+    LeadingZeros,
+    /// A number whose type suffix does not fit the expected type, like `5f32` for an integer
+    MismatchedSuffix {
+        suffix: &'static str,
+        /// The name of the expected type
+        expected: &'static str,
+    },
+    /// An enum variant index with no corresponding variant
+    VariantIndexOutOfRange {
+        index: u64,
+        variants: usize,
+    },
 
     UnexpectedByte(char),
 
@@ -58,7 +83,7 @@ impl fmt::Display for Error {
         match *self {
             Error::IoError(ref s) => write!(f, "{}", s),
             Error::Message(ref s) => write!(f, "{}", s),
-            Error::Parser(_, pos) => write!(f, "{}: {}", pos, self.description()),
+            Error::Parser(ref kind, pos) => write!(f, "{}: {}", pos, kind),
         }
     }
 }
@@ -69,40 +94,53 @@ impl de::Error for Error {
     }
 }
 
-impl StdError for Error {
-    fn description(&self) -> &str {
+impl StdError for Error {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::IoError(ref s) => s,
-            Error::Message(ref e) => e,
-            Error::Parser(ref kind, _) => match *kind {
-                ParseError::Eof => "Unexpected end of file",
-                ParseError::ExpectedArray => "Expected array",
-                ParseError::ExpectedArrayEnd => "Expected end of array",
-                ParseError::ExpectedBoolean => "Expected boolean",
-                ParseError::ExpectedComma => "Expected comma",
-                ParseError::ExpectedEnum => "Expected enum",
-                ParseError::ExpectedChar => "Expected char",
-                ParseError::ExpectedFloat => "Expected float",
-                ParseError::ExpectedInteger => "Expected integer",
-                ParseError::ExpectedOption => "Expected option",
-                ParseError::ExpectedOptionEnd => "Expected end of option",
-                ParseError::ExpectedMap => "Expected map",
-                ParseError::ExpectedMapColon => "Expected colon",
-                ParseError::ExpectedMapEnd => "Expected end of map",
-                ParseError::ExpectedStruct => "Expected struct",
-                ParseError::ExpectedStructEnd => "Expected end of struct",
-                ParseError::ExpectedUnit => "Expected unit",
-                ParseError::ExpectedStructName => "Expected struct name",
-                ParseError::ExpectedString => "Expected string",
-                ParseError::ExpectedIdentifier => "Expected identifier",
-
-                ParseError::InvalidEscape => "Invalid escape sequence",
-
-                ParseError::Utf8Error(ref e) => e.description(),
-                ParseError::TrailingCharacters => "Non-whitespace trailing characters",
-
-                _ => unimplemented!(),
-            }
+            ParseError::Eof => f.write_str("Unexpected end of file"),
+            ParseError::ExpectedArray => f.write_str("Expected array"),
+            ParseError::ExpectedArrayEnd => f.write_str("Expected end of array"),
+            ParseError::ExpectedBoolean => f.write_str("Expected boolean"),
+            ParseError::ExpectedComma => f.write_str("Expected comma"),
+            ParseError::ExpectedEnum => f.write_str("Expected enum"),
+            ParseError::ExpectedChar => f.write_str("Expected char"),
+            ParseError::ExpectedFloat => f.write_str("Expected float"),
+            ParseError::ExpectedInteger => f.write_str("Expected integer"),
+            ParseError::ExpectedOption => f.write_str("Expected option"),
+            ParseError::ExpectedOptionEnd => f.write_str("Expected end of option"),
+            ParseError::ExpectedMap => f.write_str("Expected map"),
+            ParseError::ExpectedMapColon => f.write_str("Expected colon"),
+            ParseError::ExpectedMapEnd => f.write_str("Expected end of map"),
+            ParseError::ExpectedStruct => f.write_str("Expected struct"),
+            ParseError::ExpectedStructEnd => f.write_str("Expected end of struct"),
+            ParseError::ExpectedUnit => f.write_str("Expected unit"),
+            ParseError::ExpectedStructName => f.write_str("Expected struct name"),
+            ParseError::ExpectedString => f.write_str("Expected string"),
+            ParseError::ExpectedStringEnd => f.write_str("Expected end of string"),
+            ParseError::ExpectedIdentifier => f.write_str("Expected identifier"),
+
+            ParseError::UnclosedBlockComment => f.write_str("Unclosed block comment"),
+            ParseError::InvalidEscape => f.write_str("Invalid escape sequence"),
+            ParseError::InvalidByteString => f.write_str("Invalid base64 or hex byte string"),
+            ParseError::IntegerOutOfBounds { ref value, ty, min, max } => if value.starts_with('-') {
+                write!(f, "integer {} underflows {} (min {})", value, ty, min)
+            } else {
+                write!(f, "integer {} overflows {} (max {})", value, ty, max)
+            },
+            ParseError::LeadingZeros => f.write_str("integer literals must not have leading zeros"),
+            ParseError::MismatchedSuffix { suffix, expected } =>
+                write!(f, "type suffix `{}` does not match the expected {}", suffix, expected),
+            ParseError::VariantIndexOutOfRange { index, variants } =>
+                write!(f, "variant index {} out of range for an enum with {} variants", index, variants),
+
+            ParseError::UnexpectedByte(ref b) => write!(f, "Unexpected byte {:?}", b),
+
+            ParseError::Utf8Error(ref e) => write!(f, "{}", e),
+            ParseError::TrailingCharacters => f.write_str("Non-whitespace trailing characters"),
+
+            ParseError::__NonExhaustive => unreachable!(),
         }
     }
 }
@@ -127,6 +165,18 @@ impl From<Utf8Error> for Error {
 
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Self {
-        Error::IoError(e.description().to_string())
+        Error::IoError(e.to_string())
+    }
+}
+
+impl From<ParseIntError> for Error {
+    fn from(e: ParseIntError) -> Self {
+        Error::Message(e.to_string())
     }
-}
\ No newline at end of file
+}
+
+impl From<ParseFloatError> for Error {
+    fn from(e: ParseFloatError) -> Self {
+        Error::Message(e.to_string())
+    }
+}
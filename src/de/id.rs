@@ -14,7 +14,7 @@ impl<'a, 'b: 'a> IdDeserializer<'a, 'b> {
     }
 }
 
-impl<'a, 'b: 'a, 'c> de::Deserializer<'b> for &'c mut IdDeserializer<'a, 'b> {
+impl<'a, 'b: 'a> de::Deserializer<'b> for &mut IdDeserializer<'a, 'b> {
     type Error = Error;
 
     fn deserialize_identifier<V>(
@@ -104,16 +104,16 @@ impl<'a, 'b: 'a, 'c> de::Deserializer<'b> for &'c mut IdDeserializer<'a, 'b> {
         unimplemented!("IdDeserializer may only be used for identifiers")
     }
 
-    fn deserialize_str<V>(self, _: V) -> Result<V::Value>
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'b>
     {
-        unimplemented!("IdDeserializer may only be used for identifiers")
+        self.deserialize_identifier(visitor)
     }
 
-    fn deserialize_string<V>(self, _: V) -> Result<V::Value>
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'b>
     {
-        unimplemented!("IdDeserializer may only be used for identifiers")
+        self.deserialize_identifier(visitor)
     }
 
     fn deserialize_bytes<V>(self, _: V) -> Result<V::Value>
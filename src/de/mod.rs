@@ -1,46 +1,165 @@
-/// Deserialization module.
-///
+//! Deserialization module.
 
 pub use self::error::{Error, ParseError, Result};
+pub use parse::Position;
+pub use self::peek::{peek_type, RonType};
+pub use self::stream::StreamDeserializer;
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::io;
 use std::str;
 
-use serde::de::{self, Deserializer as Deserializer_, DeserializeSeed, Visitor};
+use serde::de::{self, Deserializer as Deserializer_, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::de::value::{BorrowedStrDeserializer, MapDeserializer, SeqDeserializer};
 
-use parse::Bytes;
+use extensions::Extensions;
+use parse::{Bytes, Float, Integer};
+use value::Value;
 use self::id::IdDeserializer;
 
 mod error;
 mod id;
+mod peek;
+pub mod seed;
+mod stream;
 #[cfg(test)]
 mod tests;
 mod value;
 
+/// A map owning its keys, for deserializing RON maps with any key type.
+///
+/// Keys are deserialized like any other value, so they need not be strings:
+/// `{1: "one", 2: "two"}` deserializes into an `OwnedMap<u32, String>`.
+/// See `value::Map` for maps of dynamically typed values.
+pub type OwnedMap<K, V> = HashMap<K, V>;
+
+/// A map with string keys borrowed from the input.
+///
+/// Only keys without escape sequences can be borrowed;
+/// use `OwnedMap<String, V>` if that is a problem.
+pub type BorrowedMap<'de, V> = HashMap<&'de str, V>;
+
+/// Deserializer configuration
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct DeserializerConfig {
+    /// Extensions to enable in addition to the ones named
+    /// in `#![enable(...)]` attributes of the input
+    #[serde(default)]
+    pub extensions: Extensions,
+    /// Accept the index of an enum variant, like `2`, in place of its name
+    #[serde(default)]
+    pub deserialize_enum_as_int: bool,
+    /// Fail if the name of a struct in the input differs from the one of
+    /// the Rust type, instead of ignoring it. Names may always be left out.
+    #[serde(default)]
+    pub verify_struct_names: bool,
+    /// Accept numbers with leading zeros, like `007` or `00.7`.
+    ///
+    /// They are rejected by default since other languages read `007`
+    /// as an octal number, which RON does not have; a `0x` prefix
+    /// makes the digits hexadecimal, after which zeros are always fine.
+    #[serde(default)]
+    pub allow_leading_zeros: bool,
+}
+
 /// The RON deserializer.
 ///
 /// If you just want to simply deserialize a value,
 /// you can use the `from_str` convenience function.
 pub struct Deserializer<'de> {
     bytes: Bytes<'de>,
+    input_length: usize,
+    config: DeserializerConfig,
+    /// Set while deserializing the value of a newtype variant whose
+    /// parentheses may be merged with the ones of the variant
+    newtype_variant: bool,
+    /// The errors recovered from so far, if recovering from errors
+    /// for `from_str_all_errors`
+    errors: Option<Vec<Error>>,
 }
 
 impl<'de> Deserializer<'de> {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &'de str) -> Self {
-        Deserializer {
-            bytes: Bytes::new(input.as_bytes()),
-        }
+        Deserializer::from_bytes(input.as_bytes())
     }
 
     pub fn from_bytes(input: &'de [u8]) -> Self {
+        Deserializer::from_bytes_with_config(input, DeserializerConfig::default())
+    }
+
+    pub fn from_str_with_config(input: &'de str, config: DeserializerConfig) -> Self {
+        Deserializer::from_bytes_with_config(input.as_bytes(), config)
+    }
+
+    pub fn from_bytes_with_config(input: &'de [u8], mut config: DeserializerConfig) -> Self {
+        let mut bytes = Bytes::new(input);
+        config.extensions |= bytes.extensions();
+
         Deserializer {
-            bytes: Bytes::new(input),
+            bytes,
+            input_length: input.len(),
+            config,
+            newtype_variant: false,
+            errors: None,
         }
     }
 
-    pub fn remainder(&self) -> Cow<str> {
-        String::from_utf8_lossy(&self.bytes.bytes())
+    /// Deserializes the rest of the input as a value of type `T`,
+    /// which has to be followed by nothing but whitespace and comments.
+    ///
+    /// Useful to finish off a deserializer which was driven manually,
+    /// for example to read a tag with `deserialize_identifier` first.
+    pub fn into_serde<T>(mut self) -> Result<T>
+        where T: de::Deserialize<'de>
+    {
+        self.bytes.skip_ws()?;
+        let t = T::deserialize(&mut self)?;
+
+        self.expect_end_of_input()?;
+
+        Ok(t)
+    }
+
+    /// Turns the deserializer into an iterator over
+    /// whitespace-separated values of type `T`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<T>(self) -> StreamDeserializer<'de, T>
+        where T: de::Deserialize<'de>
+    {
+        StreamDeserializer::new(self)
+    }
+
+    pub fn remainder(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.bytes.bytes())
+    }
+
+    /// Returns the part of the input which has not been consumed yet.
+    ///
+    /// If the remaining input is not valid UTF-8 (which can only happen
+    /// when deserializing from bytes), only the valid part in front of
+    /// the first invalid byte is returned; use `remainder` to see all of it.
+    pub fn remaining_input(&self) -> &str {
+        let bytes = self.bytes.bytes();
+
+        match str::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default(),
+        }
+    }
+
+    /// Returns the length of the whole input in bytes.
+    pub fn input_length(&self) -> usize {
+        self.input_length
+    }
+
+    /// Returns the number of input bytes consumed so far.
+    ///
+    /// If the rest of the input is valid UTF-8, then
+    /// `bytes_consumed() + remaining_input().len() == input_length()`.
+    pub fn bytes_consumed(&self) -> usize {
+        self.input_length - self.bytes.bytes().len()
     }
 }
 
@@ -60,20 +179,360 @@ pub fn from_reader<R, T>(mut rdr: R) -> Result<T>
 /// and deserializing a value of type `T`.
 pub fn from_str<'a, T>(s: &'a str) -> Result<T>
     where T: de::Deserialize<'a>
+{
+    from_str_seed(s, seed::DefaultSeed::new())
+}
+
+/// Deserializes a value of type `T`, failing if anything but whitespace
+/// and comments follows it.
+///
+/// `from_str` performs the same check; use this function to make the
+/// intent explicit, e.g. to catch accidentally concatenated config files.
+pub fn from_str_check_trailing<T>(s: &str) -> Result<T>
+    where T: de::DeserializeOwned
+{
+    Deserializer::from_str(s).into_serde()
+}
+
+/// Deserializes a value of type `T`, failing if the name of a struct in the
+/// input differs from the one of the corresponding Rust type.
+///
+/// By default, struct names are ignored. Leaving them out is still allowed.
+pub fn from_str_with_struct_name_check<T>(s: &str) -> Result<T>
+    where T: de::DeserializeOwned
+{
+    let config = DeserializerConfig { verify_struct_names: true, ..Default::default() };
+
+    Deserializer::from_str_with_config(s, config).into_serde()
+}
+
+/// Deserializes a value of type `T` from the start of `s`,
+/// ignoring whatever follows it.
+///
+/// Unlike `from_str`, this accepts trailing content, for callers
+/// which intentionally read only a prefix of the input.
+pub fn from_str_partial<'a, T>(s: &'a str) -> Result<T>
+    where T: de::Deserialize<'a>
+{
+    T::deserialize(&mut Deserializer::from_str(s))
+}
+
+/// Deserializes a value of type `T` from a `Value` tree.
+///
+/// See the `Deserializer` implementation of `&Value` for how
+/// enum variants are represented.
+pub fn from_value<T>(value: &Value) -> Result<T>
+    where T: de::DeserializeOwned
+{
+    T::deserialize(value)
+}
+
+/// Same as `from_value`.
+pub fn from_ron_value<T>(value: &Value) -> Result<T>
+    where T: de::DeserializeOwned
+{
+    from_value(value)
+}
+
+/// Deserializes a value of type `T`, collecting as many errors as possible.
+///
+/// Whenever a boolean, number, char, string, option, sequence or map fails
+/// to parse, for example because it has the wrong type, the error is recorded,
+/// the value is skipped and the default value of its type is used instead:
+/// `false`, `0`, `'\0'`, `""`, `None`, or an empty sequence or map. This is
+/// the default of the primitive being parsed, not of the field it belongs to,
+/// so neither `T` nor its fields need to implement `Default`, and defaults
+/// given with `#[serde(default)]` or custom `Default` impls are not used.
+///
+/// Returns the recovered value, if any, together with all errors encountered.
+/// Errors in the structure of the input, like unbalanced brackets, cannot be
+/// recovered from.
+pub fn from_str_all_errors<T>(s: &str) -> (Option<T>, Vec<Error>)
+    where T: de::DeserializeOwned
 {
     let mut deserializer = Deserializer::from_str(s);
+    deserializer.errors = Some(Vec::new());
+
+    let res = deserializer.bytes.skip_ws()
+        .and_then(|_| T::deserialize(&mut deserializer))
+        .and_then(|t| deserializer.expect_end_of_input().map(|_| t));
+    let mut errors = deserializer.errors.take().unwrap_or_default();
+
+    match res {
+        Ok(t) => (Some(t), errors),
+        Err(e) => {
+            errors.push(e);
+
+            (None, errors)
+        }
+    }
+}
+
+/// Deserializes a value of type `T` with all extensions enabled, except
+/// `BYTES_AS_HEX` which excludes `BYTES_AS_BASE64`, whether or not the
+/// input names them in `#![enable(...)]` attributes.
+///
+/// This is meant for development and interactive tools. Don't use it in
+/// production: the extensions make the syntax ambiguous, so unusual input
+/// may be misparsed instead of being rejected.
+pub fn from_str_lenient<T>(s: &str) -> Result<T>
+    where T: de::DeserializeOwned
+{
+    let extensions = Extensions::all() - Extensions::BYTES_AS_HEX;
+    let config = DeserializerConfig { extensions, ..Default::default() };
+    let mut deserializer = Deserializer::from_str_with_config(s, config);
     let t = T::deserialize(&mut deserializer)?;
 
-    deserializer.end()?;
+    deserializer.expect_end_of_input()?;
+
+    Ok(t)
+}
+
+/// A convenience function for building a deserializer
+/// and deserializing a value using `seed`.
+pub fn from_str_seed<'a, S>(s: &'a str, seed: S) -> Result<S::Value>
+    where S: DeserializeSeed<'a>
+{
+    let mut deserializer = Deserializer::from_str(s);
+    deserializer.bytes.skip_ws()?;
+    let t = seed.deserialize(&mut deserializer)?;
+
+    deserializer.expect_end_of_input()?;
 
     Ok(t)
 }
 
 impl<'de> Deserializer<'de> {
+    /// Consumes the next value, whatever its type, without building it.
+    ///
+    /// The value is only checked to be well-formed as far as necessary
+    /// to find its end: brackets have to be balanced and strings,
+    /// chars and numbers valid.
+    pub fn skip_value(&mut self) -> Result<()> {
+        self.bytes.skip_ws()?;
+
+        match self.bytes.peek_or_eof()? {
+            b'"' => self.bytes.skip_string(),
+            b'r' if self.bytes.check_raw_string() => self.bytes.string().map(|_| ()),
+            b'\'' => self.bytes.char().map(|_| ()),
+            b'(' => self.skip_group(b')', ParseError::ExpectedStructEnd),
+            b'[' => self.skip_group(b']', ParseError::ExpectedArrayEnd),
+            b'{' => self.skip_group(b'}', ParseError::ExpectedMapEnd),
+            b'0'..=b'9' | b'+' | b'-' | b'.' => if self.bytes.check_float() {
+                match self.bytes.peek_number_suffix() {
+                    Some("f32") => self.float::<f32>().map(|_| ()),
+                    _ => self.float::<f64>().map(|_| ()),
+                }
+            } else {
+                self.check_leading_zeros()?;
+                self.bytes.skip_integer()
+            },
+            _ => {
+                // Booleans, `None`, unit variants and named structs,
+                // tuples and enum variants
+                self.bytes.identifier()?;
+                self.bytes.skip_ws()?;
+
+                if self.bytes.peek() == Some(b'(') {
+                    self.skip_group(b')', ParseError::ExpectedStructEnd)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Skips a bracketed list of values, separated by commas and closed
+    /// by `end`. In a map, each entry is a key and a value separated by
+    /// a colon; in parentheses, values may follow a field name and a colon.
+    fn skip_group(&mut self, end: u8, unclosed: ParseError) -> Result<()> {
+        let _ = self.bytes.advance_single();
+
+        loop {
+            self.bytes.skip_ws()?;
+
+            if self.bytes.peek() == Some(end) {
+                return self.bytes.advance_single();
+            }
+
+            match end {
+                b'}' => {
+                    self.skip_value()?;
+                    self.bytes.skip_ws()?;
+
+                    if !self.bytes.consume(":") {
+                        return self.bytes.err(ParseError::ExpectedMapColon);
+                    }
+                }
+                b')' => {
+                    self.bytes.field_name();
+                }
+                _ => {}
+            }
+
+            self.skip_value()?;
+            self.bytes.skip_ws()?;
+
+            if !self.bytes.consume(",") && self.bytes.peek() != Some(end) {
+                return self.bytes.err(unclosed);
+            }
+        }
+    }
+
+    /// Parses a value with `parse`. If that fails while recovering from
+    /// errors, the error is recorded and the value skipped, returning `None`
+    /// for the caller to use a default value instead.
+    fn recover<T, F>(&mut self, parse: F) -> Result<Option<T>>
+        where F: FnOnce(&mut Self) -> Result<T>
+    {
+        if self.errors.is_none() {
+            return parse(self).map(Some);
+        }
+
+        let start = self.bytes;
+        let e = match parse(self) {
+            Ok(value) => return Ok(Some(value)),
+            Err(e) => e,
+        };
+
+        self.bytes = start;
+        if self.skip_value().is_err() {
+            return Err(e);
+        }
+
+        if let Some(ref mut errors) = self.errors {
+            errors.push(e);
+        }
+
+        Ok(None)
+    }
+
+    /// Returns whether the parentheses of the current value were merged
+    /// with the ones of a newtype variant, resetting the flag for any
+    /// nested values.
+    fn take_newtype_variant(&mut self) -> bool {
+        ::std::mem::replace(&mut self.newtype_variant, false)
+    }
+
+    /// Returns whether the parentheses at the current position enclose
+    /// struct fields like `(x: 1)` rather than tuple members.
+    fn check_struct_fields(&self) -> bool {
+        let mut bytes = self.bytes;
+
+        bytes.consume("(") && bytes.skip_ws().is_ok() && bytes.field_name()
+    }
+
+    fn integer<T>(&mut self) -> Result<T>
+        where T: Integer
+    {
+        self.check_leading_zeros()?;
+        self.bytes.integer()
+    }
+
+    fn float<T>(&mut self) -> Result<T>
+        where T: Float
+    {
+        self.check_leading_zeros()?;
+        self.bytes.float()
+    }
+
+    fn check_leading_zeros(&self) -> Result<()> {
+        if !self.config.allow_leading_zeros && self.bytes.check_leading_zeros() {
+            return self.bytes.err(ParseError::LeadingZeros);
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the parentheses at the current position enclose
+    /// exactly one value, like the ones of a newtype struct.
+    fn check_single_member(&mut self) -> bool {
+        let start = self.bytes;
+        let _ = self.bytes.consume("(");
+
+        let single = self.bytes.skip_ws().is_ok() &&
+            self.bytes.peek() != Some(b')') &&
+            self.skip_value().is_ok() &&
+            {
+                self.bytes.comma();
+                self.bytes.skip_ws().is_ok() && self.bytes.peek() == Some(b')')
+            };
+        self.bytes = start;
+
+        single
+    }
+
+    /// Deserializes a number as the type named by its type suffix, if any.
+    /// Otherwise, it is deserialized as an integer if it fits into an `i64`
+    /// or `u64` and has no fractional part or exponent, and as a float if not.
+    fn deserialize_number<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.check_leading_zeros()?;
+
+        match self.bytes.peek_number_suffix() {
+            Some("i8") => return visitor.visit_i8(self.bytes.integer()?),
+            Some("i16") => return visitor.visit_i16(self.bytes.integer()?),
+            Some("i32") => return visitor.visit_i32(self.bytes.integer()?),
+            Some("i64") => return visitor.visit_i64(self.bytes.integer()?),
+            Some("i128") => return visitor.visit_i128(self.bytes.integer()?),
+            Some("u8") => return visitor.visit_u8(self.bytes.integer()?),
+            Some("u16") => return visitor.visit_u16(self.bytes.integer()?),
+            Some("u32") => return visitor.visit_u32(self.bytes.integer()?),
+            Some("u64") => return visitor.visit_u64(self.bytes.integer()?),
+            Some("f32") => return visitor.visit_f32(self.bytes.float()?),
+            _ => {}
+        }
+
+        if !self.bytes.check_float() {
+            let mut bytes = self.bytes;
+            if bytes.peek() == Some(b'-') {
+                if let Ok(i) = bytes.integer::<i64>() {
+                    self.bytes = bytes;
+                    return visitor.visit_i64(i);
+                }
+            } else if let Ok(u) = bytes.integer::<u64>() {
+                self.bytes = bytes;
+                return visitor.visit_u64(u);
+            }
+        }
+
+        self.deserialize_f64(visitor)
+    }
+
+    /// Consumes the struct name in front of the parentheses of a struct,
+    /// if there is one, checking that it is `name` if `verify_struct_names`
+    /// is set. Returns the name found.
+    fn struct_name(&mut self, name: &'static str) -> Result<Option<&'de str>> {
+        let mut bytes = self.bytes;
+        let found = match bytes.identifier() {
+            Ok(found) => str::from_utf8(found)?,
+            Err(_) => return Ok(None),
+        };
+
+        // Structs read through `deserialize_any` have no name to compare with
+        if self.config.verify_struct_names && !name.is_empty() && found != name {
+            return Err(Error::Message(format!("expected struct `{}`, found `{}`", name, found)));
+        }
+
+        self.bytes = bytes;
+        self.bytes.skip_ws()?;
+
+        Ok(Some(found))
+    }
+
     /// Check if the remaining bytes are whitespace only,
     /// otherwise return an error.
+    ///
+    /// Same as `expect_end_of_input`.
     pub fn end(&mut self) -> Result<()> {
-        self.bytes.skip_ws();
+        self.expect_end_of_input()
+    }
+
+    /// Skips whitespace and comments and returns a `TrailingCharacters`
+    /// error if anything else is left of the input.
+    pub fn expect_end_of_input(&mut self) -> Result<()> {
+        self.bytes.skip_ws()?;
 
         if self.bytes.bytes().is_empty() {
             Ok(())
@@ -83,15 +542,19 @@ impl<'de> Deserializer<'de> {
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
+        self.newtype_variant = false;
+
         if self.bytes.consume_ident("true") {
+            self.bytes.bool_annotation();
             return visitor.visit_bool(true);
         } else if self.bytes.consume_ident("false") {
+            self.bytes.bool_annotation();
             return visitor.visit_bool(false);
         } else if self.bytes.check_ident("Some") {
             return self.deserialize_option(visitor);
@@ -101,17 +564,32 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
             return visitor.visit_unit();
         }
 
-        if self.bytes.identifier().is_ok() {
-            self.bytes.skip_ws();
+        if self.bytes.check_raw_string() {
+            return self.deserialize_string(visitor);
+        }
+
+        if let Ok(ident) = self.bytes.identifier() {
+            let name = str::from_utf8(ident)?;
+            self.bytes.skip_ws()?;
+
+            // A unit struct or unit enum variant
+            if self.bytes.peek() != Some(b'(') {
+                return visitor.visit_borrowed_str(name);
+            }
 
-            return self.deserialize_struct("", &[], visitor);
+            // Named structs drop their name, while tuple structs and
+            // variants with content keep it, see `Named`
+            if !self.check_struct_fields() {
+                return visitor.visit_map(Named { de: self, name: Some(name) });
+            }
         }
 
         match self.bytes.peek_or_eof()? {
-            b'(' => self.deserialize_struct("", &[], visitor),
+            b'(' if self.check_struct_fields() => self.deserialize_struct("", &[], visitor),
+            b'(' => self.deserialize_tuple(0, visitor),
             b'[' => self.deserialize_seq(visitor),
             b'{' => self.deserialize_map(visitor),
-            b'0' ... b'9' | b'+' | b'-' | b'.' => self.deserialize_f64(visitor),
+            b'0'..=b'9' | b'+' | b'-' | b'.' => self.deserialize_number(visitor),
             b'"' => self.deserialize_string(visitor),
             b'\'' => self.deserialize_char(visitor),
             other => self.bytes.err(ParseError::UnexpectedByte(other as char)),
@@ -121,73 +599,73 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_bool(self.bytes.bool()?)
+        visitor.visit_bool(self.recover(|de| de.bytes.bool())?.unwrap_or_default())
     }
 
     fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_i8(self.bytes.signed_integer()?)
+        visitor.visit_i8(self.recover(|de| de.integer())?.unwrap_or_default())
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_i8(self.bytes.signed_integer()?)
+        visitor.visit_i16(self.recover(|de| de.integer())?.unwrap_or_default())
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_i32(self.bytes.signed_integer()?)
+        visitor.visit_i32(self.recover(|de| de.integer())?.unwrap_or_default())
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_i64(self.bytes.signed_integer()?)
+        visitor.visit_i64(self.recover(|de| de.integer())?.unwrap_or_default())
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_u8(self.bytes.unsigned_integer()?)
+        visitor.visit_u8(self.recover(|de| de.integer())?.unwrap_or_default())
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_u16(self.bytes.unsigned_integer()?)
+        visitor.visit_u16(self.recover(|de| de.integer())?.unwrap_or_default())
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_u32(self.bytes.unsigned_integer()?)
+        visitor.visit_u32(self.recover(|de| de.integer())?.unwrap_or_default())
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_u64(self.bytes.unsigned_integer()?)
+        visitor.visit_u64(self.recover(|de| de.integer())?.unwrap_or_default())
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_f32(self.bytes.float()?)
+        visitor.visit_f32(self.recover(|de| de.float())?.unwrap_or_default())
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_f64(self.bytes.float()?)
+        visitor.visit_f64(self.recover(|de| de.float())?.unwrap_or_default())
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_char(self.bytes.char()?)
+        visitor.visit_char(self.recover(|de| de.bytes.char())?.unwrap_or_default())
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
@@ -195,9 +673,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     {
         use parse::ParsedStr;
 
-        match self.bytes.string()? {
-            ParsedStr::Allocated(s) => visitor.visit_string(s),
-            ParsedStr::Slice(s) => visitor.visit_str(s),
+        match self.recover(|de| de.bytes.string())? {
+            Some(ParsedStr::Allocated(s)) => visitor.visit_string(s),
+            Some(ParsedStr::Slice(s)) => visitor.visit_borrowed_str(s),
+            None => visitor.visit_borrowed_str(""),
         }
     }
 
@@ -210,24 +689,55 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        self.deserialize_seq(visitor)
+        self.deserialize_byte_buf(visitor)
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        self.deserialize_seq(visitor)
+        use parse::{ParsedStr, decode_base64, decode_hex};
+
+        let decode: fn(&str) -> Option<Vec<u8>> = match (
+            self.config.extensions.contains(Extensions::BYTES_AS_BASE64),
+            self.config.extensions.contains(Extensions::BYTES_AS_HEX),
+        ) {
+            _ if self.bytes.peek() != Some(b'"') => return self.deserialize_seq(visitor),
+            (false, false) => return self.deserialize_seq(visitor),
+            (true, false) => decode_base64,
+            (false, true) => decode_hex,
+            (true, true) => return Err(Error::Message(
+                "the bytes_as_base64 and bytes_as_hex extensions can't be combined".to_owned()
+            )),
+        };
+
+        let decoded = match self.bytes.string()? {
+            ParsedStr::Allocated(ref s) => decode(s),
+            ParsedStr::Slice(s) => decode(s),
+        };
+
+        match decoded {
+            Some(bytes) => visitor.visit_byte_buf(bytes),
+            None => self.bytes.err(ParseError::InvalidByteString),
+        }
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        if self.bytes.consume("Some") && { self.bytes.skip_ws(); self.bytes.consume("(") } {
-            self.bytes.skip_ws();
+        self.newtype_variant = false;
+
+        if self.bytes.consume_ident("Some") {
+            self.bytes.skip_ws()?;
+
+            if !self.bytes.consume("(") {
+                return self.bytes.err(ParseError::ExpectedOption);
+            }
+
+            self.bytes.skip_ws()?;
 
             let v = visitor.visit_some(&mut *self)?;
 
-            self.bytes.skip_ws();
+            self.bytes.skip_ws()?;
 
             if self.bytes.consume(")") {
                 Ok(v)
@@ -235,10 +745,15 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
                 self.bytes.err(ParseError::ExpectedOptionEnd)
             }
 
-        } else if self.bytes.consume("None") {
+        } else if self.bytes.consume_ident("None") {
             visitor.visit_none()
+        } else if self.config.extensions.contains(Extensions::IMPLICIT_SOME) {
+            visitor.visit_some(&mut *self)
         } else {
-            self.bytes.err(ParseError::ExpectedOption)
+            let e = self.bytes.error(ParseError::ExpectedOption);
+            self.recover::<(), _>(|_| Err(e))?;
+
+            visitor.visit_none()
         }
     }
 
@@ -260,7 +775,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     ) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        if self.bytes.consume(name) {
+        if self.struct_name(name)?.is_some() {
             visitor.visit_unit()
         } else {
             self.deserialize_unit(visitor)
@@ -274,9 +789,11 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     ) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        self.bytes.consume(name);
+        if self.take_newtype_variant() || self.config.extensions.contains(Extensions::UNWRAP_NEWTYPES) {
+            return visitor.visit_newtype_struct(&mut *self);
+        }
 
-        self.bytes.skip_ws();
+        self.struct_name(name)?;
 
         if self.bytes.consume("(") {
             let value = visitor.visit_newtype_struct(&mut *self)?;
@@ -292,11 +809,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         }
     }
 
-    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
+        self.newtype_variant = false;
+
         if self.bytes.consume("[") {
-            let value = visitor.visit_seq(CommaSeparated::new(b']', &mut self))?;
+            let value = visitor.visit_seq(CommaSeparated::new(b']', self))?;
             self.bytes.comma();
 
             if self.bytes.consume("]") {
@@ -305,7 +824,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
                 self.bytes.err(ParseError::ExpectedArrayEnd)
             }
         } else {
-            self.bytes.err(ParseError::ExpectedArray)
+            let e = self.bytes.error(ParseError::ExpectedArray);
+            self.recover::<(), _>(|_| Err(e))?;
+
+            visitor.visit_seq(SeqDeserializer::new(::std::iter::empty::<()>()))
         }
     }
 
@@ -316,17 +838,19 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     // for a tuple in the Serde data model is required to know the length of the
     // tuple before even looking at the input data.
     fn deserialize_tuple<V>(
-        mut self,
+        self,
         _len: usize,
         visitor: V
     ) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        if self.bytes.consume("(") {
-            let value = visitor.visit_seq(CommaSeparated::new(b')', &mut self))?;
+        let unwrapped = self.take_newtype_variant();
+
+        if unwrapped || self.bytes.consume("(") {
+            let value = visitor.visit_seq(CommaSeparated::new(b')', self))?;
             self.bytes.comma();
 
-            if self.bytes.consume(")") {
+            if unwrapped || self.bytes.consume(")") {
                 Ok(value)
             } else {
                 self.bytes.err(ParseError::ExpectedArrayEnd)
@@ -344,15 +868,20 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     ) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        self.bytes.consume(name);
+        if !self.newtype_variant {
+            self.struct_name(name)?;
+        }
+
         self.deserialize_tuple(len, visitor)
     }
 
-    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
+        self.newtype_variant = false;
+
         if self.bytes.consume("{") {
-            let value = visitor.visit_map(CommaSeparated::new(b'}', &mut self))?;
+            let value = visitor.visit_map(CommaSeparated::new(b'}', self))?;
             self.bytes.comma();
 
             if self.bytes.consume("}") {
@@ -361,27 +890,37 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
                 self.bytes.err(ParseError::ExpectedMapEnd)
             }
         } else {
-            self.bytes.err(ParseError::ExpectedMap)
+            let e = self.bytes.error(ParseError::ExpectedMap);
+            self.recover::<(), _>(|_| Err(e))?;
+
+            visitor.visit_map(MapDeserializer::new(::std::iter::empty::<((), ())>()))
         }
     }
 
     fn deserialize_struct<V>(
-        mut self,
+        self,
         name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V
     ) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        self.bytes.consume(name);
+        let unwrapped = self.take_newtype_variant();
 
-        self.bytes.skip_ws();
+        if !unwrapped {
+            let found = self.struct_name(name)?;
 
-        if self.bytes.consume("(") {
-            let value = visitor.visit_map(CommaSeparated::new(b')', &mut self))?;
+            // Structs without fields may be written like unit structs
+            if found.is_some() && fields.is_empty() && self.bytes.peek() != Some(b'(') {
+                return visitor.visit_map(MapDeserializer::new(::std::iter::empty::<((), ())>()));
+            }
+        }
+
+        if unwrapped || self.bytes.consume("(") {
+            let value = visitor.visit_map(CommaSeparated::new(b')', self))?;
             self.bytes.comma();
 
-            if self.bytes.consume(")") {
+            if unwrapped || self.bytes.consume(")") {
                 Ok(value)
             } else {
                 self.bytes.err(ParseError::ExpectedStructEnd)
@@ -394,12 +933,14 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V
     ) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_enum(Enum::new(self))
+        self.newtype_variant = false;
+
+        visitor.visit_enum(Enum::new(self, variants))
     }
 
     fn deserialize_identifier<V>(
@@ -408,7 +949,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     ) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        visitor.visit_bytes(self.bytes.identifier()?)
+        if self.bytes.peek() == Some(b'"') || self.bytes.check_raw_string() {
+            return self.deserialize_str(visitor);
+        }
+
+        let ident = self.bytes.identifier()?;
+
+        visitor.visit_borrowed_str(str::from_utf8(ident)?)
     }
 
     fn deserialize_ignored_any<V>(
@@ -417,7 +964,20 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     ) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        self.deserialize_any(visitor)
+        // Parentheses merged with the ones of a newtype variant
+        // don't enclose a value of their own which could be skipped
+        if self.newtype_variant {
+            return self.deserialize_any(visitor);
+        }
+
+        self.skip_value()?;
+
+        visitor.visit_unit()
+    }
+
+    /// RON is a human-readable format.
+    fn is_human_readable(&self) -> bool {
+        true
     }
 }
 
@@ -437,7 +997,7 @@ impl<'a, 'de> CommaSeparated<'a, 'de> {
     }
 
     fn has_element(&mut self) -> Result<bool> {
-        self.de.bytes.skip_ws();
+        self.de.bytes.skip_ws()?;
 
         Ok(self.had_comma &&
            self.de.bytes.peek_or_eof()? != self.terminator)
@@ -482,10 +1042,10 @@ impl<'de, 'a> de::MapAccess<'de> for CommaSeparated<'a, 'de> {
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
         where V: DeserializeSeed<'de>
     {
-        self.de.bytes.skip_ws();
+        self.de.bytes.skip_ws()?;
 
         if self.de.bytes.consume(":") {
-            self.de.bytes.skip_ws();
+            self.de.bytes.skip_ws()?;
 
             let res = seed.deserialize(&mut *self.de)?;
 
@@ -498,13 +1058,81 @@ impl<'de, 'a> de::MapAccess<'de> for CommaSeparated<'a, 'de> {
     }
 }
 
+/// A tuple struct or enum variant with a name, like `Point(1, 2)` or `B(true)`,
+/// read through `deserialize_any`.
+///
+/// Like `ser::to_ron_value` represents enum variants, it is a map with
+/// a single entry from the name to the content. Named structs with fields
+/// are read as just their fields instead, so struct variants lose their name.
+struct Named<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    /// The name, until its entry's key was read
+    name: Option<&'de str>,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for Named<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: DeserializeSeed<'de>
+    {
+        match self.name.take() {
+            Some(name) => seed.deserialize(BorrowedStrDeserializer::new(name)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: DeserializeSeed<'de>
+    {
+        seed.deserialize(NamedContent { de: &mut *self.de })
+    }
+}
+
+/// The parentheses following the name of a `Named`: a single value
+/// is just that value and several are a sequence.
+struct NamedContent<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for NamedContent<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        if !self.de.check_single_member() {
+            return self.de.deserialize_tuple(0, visitor);
+        }
+
+        let _ = self.de.bytes.consume("(");
+        self.de.bytes.skip_ws()?;
+        let value = self.de.deserialize_any(visitor)?;
+        self.de.bytes.comma();
+
+        if self.de.bytes.consume(")") {
+            Ok(value)
+        } else {
+            self.de.bytes.err(ParseError::ExpectedStructEnd)
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
 struct Enum<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
+    /// The variant names, for looking up variants given by index
+    variants: &'static [&'static str],
 }
 
 impl<'a, 'de> Enum<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>) -> Self {
-        Enum { de }
+    fn new(de: &'a mut Deserializer<'de>, variants: &'static [&'static str]) -> Self {
+        Enum { de, variants }
     }
 }
 
@@ -515,7 +1143,25 @@ impl<'de, 'a> de::EnumAccess<'de> for Enum<'a, 'de> {
     fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
         where V: DeserializeSeed<'de>
     {
-        let value = seed.deserialize(&mut *self.de)?;
+        let by_index = self.de.config.deserialize_enum_as_int &&
+            self.de.bytes.peek().is_some_and(|b| b.is_ascii_digit());
+
+        if !by_index {
+            let value = seed.deserialize(&mut *self.de)?;
+
+            return Ok((value, self));
+        }
+
+        let start = self.de.bytes;
+        let index: u64 = self.de.integer()?;
+        let variant = match self.variants.get(index as usize) {
+            Some(variant) => variant,
+            None => return start.err(ParseError::VariantIndexOutOfRange {
+                index,
+                variants: self.variants.len(),
+            }),
+        };
+        let value = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(*variant))?;
 
         Ok((value, self))
     }
@@ -531,10 +1177,12 @@ impl<'de, 'a> de::VariantAccess<'de> for Enum<'a, 'de> {
     fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
         where T: DeserializeSeed<'de>
     {
-        self.de.bytes.skip_ws();
+        self.de.bytes.skip_ws()?;
         
         if self.de.bytes.consume("(") {
+            self.de.newtype_variant = self.de.config.extensions.contains(Extensions::UNWRAP_VARIANT_NEWTYPES);
             let val = seed.deserialize(&mut *self.de)?;
+            self.de.newtype_variant = false;
 
             self.de.bytes.comma();
 
@@ -551,7 +1199,7 @@ impl<'de, 'a> de::VariantAccess<'de> for Enum<'a, 'de> {
     fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        self.de.bytes.skip_ws();
+        self.de.bytes.skip_ws()?;
 
         self.de.deserialize_tuple(len, visitor)
     }
@@ -563,7 +1211,7 @@ impl<'de, 'a> de::VariantAccess<'de> for Enum<'a, 'de> {
     ) -> Result<V::Value>
         where V: Visitor<'de>
     {
-        self.de.bytes.skip_ws();
+        self.de.bytes.skip_ws()?;
 
         self.de.deserialize_struct("", fields, visitor)
     }
@@ -0,0 +1,66 @@
+use std::str;
+
+use super::{Deserializer, Result};
+use de::ParseError;
+
+/// The kind of a RON value, as far as it can be told from its first token.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum RonType {
+    Bool,
+    Integer,
+    Float,
+    Char,
+    String,
+    Option,
+    Unit,
+    Seq,
+    Map,
+    /// A struct, tuple or tuple struct, with its name
+    /// (empty if it has none)
+    Struct(String),
+    /// An identifier without parentheses, like a unit
+    /// enum variant or a unit struct
+    EnumVariant(String),
+}
+
+/// Tells the type of the value in `s` from its first token, without parsing
+/// the value itself. Leading `#![enable(...)]` attributes are skipped.
+///
+/// A name followed by parentheses is reported as a `Struct`, as RON
+/// does not distinguish named structs from enum variants with fields.
+pub fn peek_type(s: &str) -> Result<RonType> {
+    let mut bytes = Deserializer::from_str(s).bytes;
+
+    if bytes.check_ident("true") || bytes.check_ident("false") {
+        return Ok(RonType::Bool);
+    } else if bytes.check_ident("Some") || bytes.check_ident("None") {
+        return Ok(RonType::Option);
+    } else if bytes.consume("()") {
+        return Ok(RonType::Unit);
+    } else if bytes.check_raw_string() {
+        return Ok(RonType::String);
+    }
+
+    match bytes.peek_or_eof()? {
+        b'(' => Ok(RonType::Struct(String::new())),
+        b'[' => Ok(RonType::Seq),
+        b'{' => Ok(RonType::Map),
+        b'"' => Ok(RonType::String),
+        b'\'' => Ok(RonType::Char),
+        b'0'..=b'9' | b'+' | b'-' | b'.' if bytes.check_float() => Ok(RonType::Float),
+        b'0'..=b'9' | b'+' | b'-' | b'.' => Ok(RonType::Integer),
+        other => {
+            let name = match bytes.identifier() {
+                Ok(ident) => str::from_utf8(ident)?.to_owned(),
+                Err(_) => return bytes.err(ParseError::UnexpectedByte(other as char)),
+            };
+            bytes.skip_ws()?;
+
+            if bytes.peek() == Some(b'(') {
+                Ok(RonType::Struct(name))
+            } else {
+                Ok(RonType::EnumVariant(name))
+            }
+        }
+    }
+}
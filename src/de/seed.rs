@@ -0,0 +1,34 @@
+//! Helpers for deserializing with `DeserializeSeed`.
+
+use std::marker::PhantomData;
+
+use serde::de::{Deserialize, DeserializeSeed, Deserializer};
+
+/// A seed which deserializes `T` using its `Deserialize` implementation.
+///
+/// This is useful for generic code which requires a `DeserializeSeed`.
+pub struct DefaultSeed<T>(PhantomData<T>);
+
+impl<T> DefaultSeed<T> {
+    pub fn new() -> Self {
+        DefaultSeed(PhantomData)
+    }
+}
+
+impl<T> Default for DefaultSeed<T> {
+    fn default() -> Self {
+        DefaultSeed::new()
+    }
+}
+
+impl<'de, T> DeserializeSeed<'de> for DefaultSeed<T>
+    where T: Deserialize<'de>
+{
+    type Value = T;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T, D::Error>
+        where D: Deserializer<'de>
+    {
+        T::deserialize(deserializer)
+    }
+}
@@ -0,0 +1,48 @@
+use std::marker::PhantomData;
+
+use serde::de::Deserialize;
+
+use super::{Deserializer, Result};
+
+/// An iterator over the whitespace-separated values of a RON input.
+///
+/// Iteration stops after the end of the input or the first error.
+pub struct StreamDeserializer<'de, T> {
+    de: Deserializer<'de>,
+    failed: bool,
+    output: PhantomData<T>,
+}
+
+impl<'de, T> StreamDeserializer<'de, T>
+    where T: Deserialize<'de>
+{
+    pub fn new(de: Deserializer<'de>) -> Self {
+        StreamDeserializer {
+            de,
+            failed: false,
+            output: PhantomData,
+        }
+    }
+}
+
+impl<'de, T> Iterator for StreamDeserializer<'de, T>
+    where T: Deserialize<'de>
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed {
+            return None;
+        }
+
+        let ws = self.de.bytes.skip_ws();
+        if ws.is_ok() && self.de.bytes.bytes().is_empty() {
+            return None;
+        }
+
+        let res = ws.and_then(|_| T::deserialize(&mut self.de));
+        self.failed = res.is_err();
+
+        Some(res)
+    }
+}
@@ -21,9 +21,91 @@ enum MyEnum {
 fn test_empty_struct() {
     assert_eq!(Ok(EmptyStruct1), from_str("EmptyStruct1"));
     assert_eq!(Ok(EmptyStruct2 {}), from_str("EmptyStruct2()"));
+    assert_eq!(Ok(EmptyStruct2 {}), from_str("EmptyStruct2"));
+    assert_eq!(Ok(EmptyStruct2 {}), from_str("()"));
+    assert_eq!(Ok(vec![EmptyStruct2 {}, EmptyStruct2 {}]), from_str("[EmptyStruct2, EmptyStruct2 ( )]"));
+    assert_eq!(Ok(EmptyStruct2 {}), from_str("EmptyStruct"));
+    assert_eq!(Err(Error::Message("expected struct `EmptyStruct2`, found `EmptyStruct`".to_owned())),
+               from_str_with_struct_name_check::<EmptyStruct2>("EmptyStruct"));
+    assert_eq!(err(ParseError::ExpectedStruct, 1, 9), from_str::<MyStruct>("MyStruct"));
 }
 
 
+#[test]
+fn test_identifier() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Idents {
+        _a1: i32,
+        #[serde(rename = "true")]
+        yes: i32,
+        #[serde(rename = "None")]
+        none: i32,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Keyword {
+        Some,
+        None,
+        _Private2,
+    }
+
+    assert_eq!(Ok(Idents { _a1: 1, yes: 2, none: 3 }), from_str("(_a1: 1, true: 2, None: 3)"));
+    assert_eq!(Ok(Idents { _a1: 1, yes: 2, none: 3 }), from_str("(\"_a1\": 1, \"true\": 2, None: 3)"));
+    assert_eq!(Ok(vec![Keyword::Some, Keyword::None, Keyword::_Private2]),
+               from_str("[Some, None, _Private2]"));
+    assert_eq!(Ok(Keyword::None), from_str("\"None\""));
+    assert_eq!(err(ParseError::ExpectedIdentifier, 1, 1), from_str::<Keyword>("1A"));
+}
+
+#[test]
+fn test_borrowed_identifier() {
+    struct Borrowed;
+
+    impl<'de> Visitor<'de> for Borrowed {
+        type Value = &'de str;
+
+        fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+            f.write_str("a borrowed identifier")
+        }
+
+        fn visit_borrowed_str<E>(self, v: &'de str) -> ::std::result::Result<&'de str, E> {
+            Ok(v)
+        }
+    }
+
+    let mut de = Deserializer::from_str("field_1 ");
+    assert_eq!(Ok("field_1"), de.deserialize_identifier(Borrowed));
+}
+
+#[test]
+fn test_into_serde() {
+    struct Tag;
+
+    impl<'de> Visitor<'de> for Tag {
+        type Value = String;
+
+        fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+            f.write_str("a shape tag")
+        }
+
+        fn visit_str<E>(self, v: &str) -> ::std::result::Result<String, E> {
+            Ok(v.to_string())
+        }
+    }
+
+    fn area(s: &str) -> Result<f32> {
+        let mut de = Deserializer::from_str(s);
+        match de.deserialize_identifier(Tag)?.as_str() {
+            "circle" => de.into_serde::<f32>().map(|r| 3.0 * r * r),
+            _ => de.into_serde::<(f32, f32)>().map(|(w, h)| w * h),
+        }
+    }
+
+    assert_eq!(area("circle 2"), Ok(12.0));
+    assert_eq!(area("rect (2, 3.5) // done"), Ok(7.0));
+    assert_eq!(area("rect (2, 3) x"), err(ParseError::TrailingCharacters, 1, 13));
+}
+
 #[test]
 fn test_struct() {
     let my_struct = MyStruct { x: 4.0, y: 7.0 };
@@ -45,10 +127,103 @@ fn test_struct() {
 }
 
 
+#[test]
+fn test_comments() {
+    let my_struct = MyStruct { x: 4.0, y: 7.0 };
+
+    assert_eq!(Ok(my_struct), from_str("// line\nMyStruct( /* x */ x: 4, // y\n y: /* multi\nline */ 7)"));
+    assert_eq!(Ok(my_struct), from_str("/**/(x: 4, y: 7)/* trailing **/"));
+    assert_eq!(err(ParseError::UnclosedBlockComment, 1, 8), from_str::<MyStruct>("(x: 4, /*\ny: 7)   "));
+    assert_eq!(err(ParseError::UnclosedBlockComment, 1, 4), from_str::<i32>("42 /* oops"));
+
+    assert_eq!(Ok(42), from_str("/* a /* b */ c */ 42 /*/**/*/"));
+    assert_eq!(err(ParseError::UnclosedBlockComment, 1, 1), from_str::<i32>("/* a /* b */ 42"));
+}
+
 #[test]
 fn test_option() {
     assert_eq!(Ok(Some(1u8)), from_str("Some(1)"));
     assert_eq!(Ok(None::<u8>), from_str("None"));
+    assert_eq!(Ok(Some(Some(1u8))), from_str("Some( Some (1) )"));
+}
+
+#[test]
+fn test_option_strict() {
+    assert_eq!(err(ParseError::ExpectedOption, 1, 1), from_str::<Option<u32>>("42"));
+    assert_eq!(err(ParseError::ExpectedOption, 1, 6), from_str::<Option<u32>>("Some 42"));
+    assert_eq!(err(ParseError::ExpectedOption, 1, 6), from_str::<Option<Option<u32>>>("Some(42)"));
+}
+
+#[test]
+fn test_tuple_struct_names() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Pair(i32, i32);
+
+    fn parse(s: &str, verify_struct_names: bool) -> Result<Pair> {
+        let config = DeserializerConfig { verify_struct_names, ..Default::default() };
+        Deserializer::from_str_with_config(s, config).into_serde()
+    }
+
+    for &verify in &[false, true] {
+        assert_eq!(parse("Pair(1, 2)", verify), Ok(Pair(1, 2)));
+        assert_eq!(parse("Pair (1, 2,)", verify), Ok(Pair(1, 2)));
+        assert_eq!(parse("(1, 2)", verify), Ok(Pair(1, 2)));
+        assert_eq!(parse("Pair[1, 2]", verify), err(ParseError::ExpectedArray, 1, 5));
+    }
+
+    assert_eq!(parse("Couple(1, 2)", false), Ok(Pair(1, 2)));
+    assert_eq!(parse("Couple(1, 2)", true),
+               Err(Error::Message("expected struct `Pair`, found `Couple`".to_string())));
+    assert_eq!(parse("PairPair(1, 2)", true),
+               Err(Error::Message("expected struct `Pair`, found `PairPair`".to_string())));
+}
+
+#[test]
+fn test_struct_name_check() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Unit;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Meters(f32);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Outer {
+        inner: MyStruct,
+        unit: Unit,
+        length: Meters,
+    }
+
+    let expected = Outer { inner: MyStruct { x: 1.0, y: 2.0 }, unit: Unit, length: Meters(3.0) };
+
+    let named = "Outer(inner: MyStruct(x: 1, y: 2), unit: Unit, length: Meters(3))";
+    assert_eq!(from_str_with_struct_name_check(named), Ok(expected));
+    let anonymous = "(inner: (x: 1, y: 2), unit: (), length: (3))";
+    let expected = Outer { inner: MyStruct { x: 1.0, y: 2.0 }, unit: Unit, length: Meters(3.0) };
+    assert_eq!(from_str_with_struct_name_check(anonymous), Ok(expected));
+
+    let mismatches = [
+        ("Config(inner: (x: 1, y: 2), unit: (), length: (3))", "Outer", "Config"),
+        ("(inner: Point(x: 1, y: 2), unit: (), length: (3))", "MyStruct", "Point"),
+        ("(inner: (x: 1, y: 2), unit: Nothing, length: (3))", "Unit", "Nothing"),
+        ("(inner: (x: 1, y: 2), unit: (), length: Feet(3))", "Meters", "Feet"),
+    ];
+    for &(s, expected, found) in &mismatches {
+        let message = format!("expected struct `{}`, found `{}`", expected, found);
+        assert_eq!(from_str_with_struct_name_check::<Outer>(s), Err(Error::Message(message)));
+        assert!(from_str::<Outer>(s).is_ok(), "{}", s);
+    }
+}
+
+#[test]
+fn test_option_implicit_some() {
+    let config = DeserializerConfig { extensions: Extensions::IMPLICIT_SOME, ..Default::default() };
+    let mut de = Deserializer::from_str_with_config("42", config);
+    assert_eq!(Ok(Some(42)), <Option<u32> as de::Deserialize>::deserialize(&mut de));
+
+    assert_eq!(Ok(Some(42u32)), from_str("#![enable(implicit_some)] 42"));
+    assert_eq!(Ok(Some(Some(42u32))), from_str("#![enable(implicit_some)] 42"));
+    assert_eq!(Ok(Some(None::<u32>)), from_str("#![enable(implicit_some)] Some(None)"));
+    assert_eq!(Ok(None::<u32>), from_str("#![enable(implicit_some)] None"));
 }
 
 #[test]
@@ -59,6 +234,47 @@ fn test_enum() {
     assert_eq!(Ok(MyEnum::D { a: 2, b: 3 }), from_str("D(a:2,b:3,)"));
 }
 
+#[test]
+fn test_enum_as_int() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Shape {
+        Dot,
+        Circle(f32),
+        Rect { w: u32, h: u32 },
+    }
+
+    fn parse(s: &str) -> Result<Vec<Shape>> {
+        let config = DeserializerConfig { deserialize_enum_as_int: true, ..Default::default() };
+        Deserializer::from_str_with_config(s, config).into_serde()
+    }
+
+    let shapes = vec![Shape::Dot, Shape::Circle(0.5), Shape::Rect { w: 2, h: 3 }];
+    assert_eq!(parse("[Dot, Circle(0.5), Rect(w: 2, h: 3)]"), Ok(shapes));
+    let shapes = vec![Shape::Dot, Shape::Circle(0.5), Shape::Rect { w: 2, h: 3 }];
+    assert_eq!(parse("[0, 1(0.5), 2(w: 2, h: 3)]"), Ok(shapes));
+    let shapes = vec![Shape::Rect { w: 1, h: 1 }, Shape::Dot];
+    assert_eq!(parse("[2 (h: 1, w: 1), Dot]"), Ok(shapes));
+
+    assert_eq!(parse("[0, 3]"), err(ParseError::VariantIndexOutOfRange { index: 3, variants: 3 }, 1, 5));
+    assert_eq!(parse("[99999999999999999999]"),
+               err(ParseError::IntegerOutOfBounds {
+                   value: "99999999999999999999".to_string(),
+                   ty: "u64",
+                   min: 0,
+                   max: u64::MAX as i128,
+               }, 1, 2));
+    assert!(from_str::<Shape>("0").is_err());
+}
+
+#[test]
+fn test_struct_variant() {
+    assert_eq!(Ok(MyEnum::D { a: 2, b: 3 }), from_str("D(a:2,b:3)"));
+    assert_eq!(Ok(MyEnum::D { a: 2, b: 3 }), from_str("D (\n    b: 3,\n    a: 2,\n)"));
+    assert_eq!(Ok(vec![MyEnum::D { a: 1, b: 2 }, MyEnum::A]), from_str("[D(a:1,b:2),A]"));
+
+    assert_eq!(err(ParseError::ExpectedIdentifier, 1, 3), from_str::<MyEnum>("D(2,3)"));
+}
+
 #[test]
 fn test_array() {
     let empty: [i32; 0] = [];
@@ -67,7 +283,7 @@ fn test_array() {
     assert_eq!(Ok(empty_array), from_str("[]"));
 
     assert_eq!(Ok([2, 3, 4i32]), from_str("(2,3,4,)"));
-    assert_eq!(Ok(([2, 3, 4i32].to_vec())), from_str("[2,3,4,]"));
+    assert_eq!(Ok([2, 3, 4i32].to_vec()), from_str("[2,3,4,]"));
 }
 
 #[test]
@@ -84,6 +300,26 @@ fn test_map() {
     }"));
 }
 
+#[test]
+fn test_map_keys() {
+    use std::collections::BTreeMap;
+
+    let map: BTreeMap<u32, String> = vec![(1, "one".to_string()), (20, "twenty".to_string())]
+        .into_iter()
+        .collect();
+    assert_eq!(Ok(map), from_str("{20: \"twenty\", 1: \"one\"}"));
+
+    let map: OwnedMap<char, MyEnum> = vec![('a', MyEnum::A), ('b', MyEnum::B(true))].into_iter().collect();
+    assert_eq!(Ok(map), from_str("{'a': A, 'b': B(true)}"));
+
+    let input = "{\"x\": 1.5, \"y\": -2}";
+    let map: BorrowedMap<f32> = from_str(input).unwrap();
+    assert_eq!(map["x"], 1.5);
+    assert_eq!(map["y"], -2.0);
+
+    assert_eq!(err(ParseError::ExpectedInteger, 1, 2), from_str::<OwnedMap<u8, u8>>("{\"1\": 1}"));
+}
+
 #[test]
 fn test_string() {
     let s: String = from_str("\"String\"").unwrap();
@@ -117,8 +353,6 @@ y: 2.0 // 2!
 }
 
 fn err<T>(kind: ParseError, line: usize, col: usize) -> Result<T> {
-    use parse::Position;
-
     Err(Error::Parser(kind, Position { line, col }))
 }
 
@@ -161,6 +395,7 @@ fn untagged() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn forgot_apostrophes() {
     let de: Result<(i32, String)> = from_str("(4, \"Hello)");
 
@@ -169,3 +404,623 @@ fn forgot_apostrophes() {
         _ => false,
     });
 }
+
+#[test]
+fn test_remaining_input() {
+    use serde::Deserialize;
+
+    let mut de = Deserializer::from_str("42 extra");
+    assert_eq!(de.remaining_input(), "42 extra");
+
+    assert_eq!(u32::deserialize(&mut de), Ok(42));
+    assert_eq!(de.remaining_input(), " extra");
+    assert!(de.end().is_err());
+
+    let mut de = Deserializer::from_bytes(b"1 \xff 2");
+    assert_eq!(u8::deserialize(&mut de), Ok(1));
+    assert_eq!(de.remaining_input(), " ");
+    assert_eq!(de.remainder(), " \u{fffd} 2");
+    assert_eq!(de.bytes_consumed(), 1);
+}
+
+#[test]
+fn test_bytes_consumed() {
+    use serde::Deserialize;
+
+    let input = "  (1, 2) [3, 4] // done\n";
+    let mut de = Deserializer::from_str(input);
+    let check = |de: &Deserializer, consumed: usize| {
+        assert_eq!(de.input_length(), input.len());
+        assert_eq!(de.bytes_consumed(), consumed);
+        assert_eq!(de.bytes_consumed() + de.remaining_input().len(), de.input_length());
+    };
+
+    // Leading whitespace is skipped right away
+    check(&de, 2);
+
+    assert_eq!(<(u8, u8)>::deserialize(&mut de), Ok((1, 2)));
+    check(&de, 8);
+
+    de.bytes.skip_ws().unwrap();
+    assert_eq!(Vec::<u8>::deserialize(&mut de), Ok(vec![3, 4]));
+    check(&de, 15);
+
+    assert_eq!(de.end(), Ok(()));
+    check(&de, input.len());
+}
+
+#[test]
+fn test_integer_overflow() {
+    assert_eq!(from_str::<u64>("99999999999999999999"), err(ParseError::IntegerOutOfBounds {
+        value: "99999999999999999999".to_owned(),
+        ty: "u64",
+        min: 0,
+        max: u64::MAX as i128,
+    }, 1, 1));
+    assert!(from_str::<i64>("-99999999999999999999").is_err());
+}
+
+#[test]
+fn test_parse_num_error() {
+    let e: Error = "99999999999999999999".parse::<u64>().unwrap_err().into();
+    assert_eq!(e, Error::Message("number too large to fit in target type".to_owned()));
+
+    let e: Error = "1.x".parse::<f64>().unwrap_err().into();
+    assert_eq!(e, Error::Message("invalid float literal".to_owned()));
+}
+
+#[test]
+fn test_stream() {
+    let de = Deserializer::from_str_with_config("1\n2\n3", DeserializerConfig::default());
+    let values: Result<Vec<i32>> = de.into_iter().collect();
+    assert_eq!(values, Ok(vec![1, 2, 3]));
+
+    let de = Deserializer::from_str("(x: 1, y: 2) // first\n MyStruct(x: 3, y: 4)\n");
+    let values: Result<Vec<MyStruct>> = de.into_iter().collect();
+    assert_eq!(values, Ok(vec![MyStruct { x: 1.0, y: 2.0 }, MyStruct { x: 3.0, y: 4.0 }]));
+
+    let mut iter = Deserializer::from_str("1 true 3").into_iter::<i32>();
+    assert_eq!(iter.next(), Some(Ok(1)));
+    assert_eq!(iter.next(), Some(err(ParseError::ExpectedInteger, 1, 3)));
+    assert_eq!(iter.next(), None);
+
+    assert_eq!(Deserializer::from_str("  ").into_iter::<i32>().next(), None);
+}
+
+#[test]
+fn test_default_seed() {
+    use self::seed::DefaultSeed;
+
+    assert_eq!(from_str_seed("42", DefaultSeed::<u32>::new()), Ok(42u32));
+    assert_eq!(from_str_seed("D(a: 2, b: 3)", DefaultSeed::<MyEnum>::default()),
+               Ok(MyEnum::D { a: 2, b: 3 }));
+    assert_eq!(from_str_seed("42 43", DefaultSeed::<u32>::new()),
+               err(ParseError::TrailingCharacters, 1, 4));
+}
+
+#[test]
+fn test_end_of_input() {
+    assert_eq!(from_str::<i32>("42 oops"), err(ParseError::TrailingCharacters, 1, 4));
+    assert_eq!(from_str("42 // ok"), Ok(42));
+    assert_eq!(from_str("42 /* ok */\n\n"), Ok(42));
+    assert_eq!(from_str_partial("42 oops"), Ok(42));
+    assert_eq!(from_str_partial::<i32>("oops"), err(ParseError::ExpectedInteger, 1, 1));
+
+    let mut de = Deserializer::from_str("(1, 2) // end\n(3, 4)");
+    let t: (u8, u8) = de::Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(t, (1, 2));
+    assert_eq!(de.expect_end_of_input(), err(ParseError::TrailingCharacters, 2, 1));
+}
+
+#[test]
+fn test_check_trailing() {
+    assert_eq!(from_str_check_trailing("[1, 2] \n\t "), Ok(vec![1, 2]));
+    assert_eq!(from_str_check_trailing("[1, 2] // two\n/* numbers */"), Ok(vec![1, 2]));
+    assert_eq!(from_str_check_trailing::<Vec<u8>>("[1, 2]\n[3]"), err(ParseError::TrailingCharacters, 2, 1));
+    assert_eq!(from_str_check_trailing::<MyStruct>("(x: 1, y: 2) 3"), err(ParseError::TrailingCharacters, 1, 14));
+}
+
+#[test]
+fn test_char_escapes() {
+    let escaped = [
+        ("'\\n'", '\n'),
+        ("'\\t'", '\t'),
+        ("'\\r'", '\r'),
+        ("'\\\\'", '\\'),
+        ("'\\''", '\''),
+        ("'\\\"'", '"'),
+        ("'\\0'", '\0'),
+        ("'\\x41'", 'A'),
+        ("'\\x7f'", '\x7f'),
+        ("'\\u{1F600}'", '\u{1F600}'),
+        ("'\\u{e9}'", 'é'),
+        ("'\\u{000041}'", 'A'),
+        ("'é'", 'é'),
+        ("'😀'", '😀'),
+        ("'\"'", '"'),
+    ];
+
+    for &(s, c) in escaped.iter() {
+        assert_eq!(from_str::<char>(s), Ok(c), "{}", s);
+    }
+}
+
+#[test]
+fn test_char_roundtrip() {
+    use ser::to_string;
+
+    let chars = ['a', ' ', '\n', '\t', '\r', '\\', '\'', '"', '\0', '\x01', '\x1b', '\x7f', '\u{85}',
+                 'é', 'ß', '€', '\u{200B}', '😀', '\u{10FFFF}'];
+
+    for &c in chars.iter() {
+        let s = to_string(&c).unwrap();
+        assert_eq!(from_str::<char>(&s), Ok(c), "{:?}", c);
+        assert!(!s.chars().any(char::is_control), "{:?}", s);
+    }
+
+    let s = to_string(&chars.to_vec()).unwrap();
+    assert_eq!(from_str::<Vec<char>>(&s), Ok(chars.to_vec()));
+}
+
+#[test]
+fn test_invalid_char() {
+    use self::ParseError::*;
+
+    assert_eq!(from_str::<char>("'\\z'"), err(InvalidEscape, 1, 4));
+    assert_eq!(from_str::<char>("'\\x80'"), err(InvalidEscape, 1, 6));
+    assert_eq!(from_str::<char>("'\\x4'"), err(InvalidEscape, 1, 4));
+    assert_eq!(from_str::<char>("'\\u{D800}'"), err(InvalidEscape, 1, 10));
+    assert_eq!(from_str::<char>("'\\u{110000}'"), err(InvalidEscape, 1, 12));
+    assert_eq!(from_str::<char>("'\\u{1234567}'"), err(InvalidEscape, 1, 5));
+    assert_eq!(from_str::<char>("'\\u41'"), err(InvalidEscape, 1, 4));
+    assert_eq!(from_str::<char>("'ab'"), err(ExpectedChar, 1, 3));
+    assert_eq!(from_str::<char>("''"), err(ExpectedChar, 1, 3));
+    assert_eq!(from_str::<char>("'''"), err(ExpectedChar, 1, 3));
+    assert_eq!(from_str::<char>("'"), err(Eof, 1, 2));
+}
+
+#[test]
+fn test_all_errors() {
+    #[derive(Debug, Default, PartialEq, Deserialize)]
+    #[serde(default)]
+    struct Inner {
+        a: u8,
+        b: String,
+    }
+
+    #[derive(Debug, Default, PartialEq, Deserialize)]
+    #[serde(default)]
+    struct Config {
+        name: String,
+        port: u16,
+        inner: Inner,
+        tags: Vec<String>,
+    }
+
+    let (value, errors) = from_str_all_errors::<Config>("Config(
+    name: \"server\",
+    port: true,
+    inner: (a: 3, b: 'x'),
+    tags: [\"a\", \"b\"],
+)");
+    assert_eq!(value, Some(Config {
+        name: "server".to_string(),
+        port: 0,
+        inner: Inner { a: 3, b: String::new() },
+        tags: vec!["a".to_string(), "b".to_string()],
+    }));
+    assert_eq!(errors, vec![
+        Error::Parser(ParseError::ExpectedInteger, Position { line: 3, col: 11 }),
+        Error::Parser(ParseError::ExpectedString, Position { line: 4, col: 22 }),
+    ]);
+
+    let (value, errors) = from_str_all_errors::<Config>("(tags: [1], port: 80)");
+    assert_eq!(value, Some(Config { port: 80, tags: vec![String::new()], ..Config::default() }));
+    assert_eq!(errors, vec![Error::Parser(ParseError::ExpectedString, Position { line: 1, col: 9 })]);
+
+    let (value, errors) = from_str_all_errors::<Config>("(name: r\"a)b\", port: /* ) */ 'p', tags: 7)");
+    assert_eq!(value, Some(Config { name: "a)b".to_string(), ..Config::default() }));
+    assert_eq!(errors, vec![
+        Error::Parser(ParseError::ExpectedInteger, Position { line: 1, col: 30 }),
+        Error::Parser(ParseError::ExpectedArray, Position { line: 1, col: 41 }),
+    ]);
+
+    let (value, errors) = from_str_all_errors::<Config>("(port: 80");
+    assert_eq!(value, None);
+    assert_eq!(errors, vec![Error::Parser(ParseError::ExpectedStructEnd, Position { line: 1, col: 10 })]);
+
+    assert_eq!(from_str_all_errors::<Config>("(port: 80)"),
+               (Some(Config { port: 80, ..Config::default() }), vec![]));
+
+    let (value, errors) = from_str_all_errors::<Config>("[1, 2]");
+    assert_eq!(value, None);
+    assert_eq!(errors, vec![Error::Parser(ParseError::ExpectedStruct, Position { line: 1, col: 1 })]);
+
+    // Fields fall back to their default value even without `#[serde(default)]`
+    let (value, errors) = from_str_all_errors::<MyStruct>("(x: 1, y: false)");
+    assert_eq!(value, Some(MyStruct { x: 1.0, y: 0.0 }));
+    assert_eq!(errors, vec![Error::Parser(ParseError::ExpectedFloat, Position { line: 1, col: 11 })]);
+
+    let (value, errors) = from_str_all_errors::<MyStruct>("(x: 1)");
+    assert_eq!(value, None);
+    assert_eq!(errors.len(), 1);
+
+    // The default comes from the primitive, not from the struct's `Default`
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(default)]
+    struct Server {
+        port: u16,
+    }
+
+    impl Default for Server {
+        fn default() -> Self {
+            Server { port: 8080 }
+        }
+    }
+
+    assert_eq!(from_str_all_errors::<Server>("()").0, Some(Server { port: 8080 }));
+    assert_eq!(from_str_all_errors::<Server>("(port: \"80\")").0, Some(Server { port: 0 }));
+}
+
+#[test]
+fn test_skip_value() {
+    fn skip(s: &str) -> Result<String> {
+        let mut de = Deserializer::from_str(s);
+        de.skip_value()?;
+        de.bytes.skip_ws().unwrap();
+
+        Ok(de.remaining_input().to_owned())
+    }
+
+    assert_eq!(skip("true, 1"), Ok(", 1".to_owned()));
+    assert_eq!(skip("None]"), Ok("]".to_owned()));
+    assert_eq!(skip("-1.5e3 2"), Ok("2".to_owned()));
+    assert_eq!(skip("'\\'' 2"), Ok("2".to_owned()));
+    assert_eq!(skip("\"a \\\" ) ]\" 2"), Ok("2".to_owned()));
+    assert_eq!(skip("() 2"), Ok("2".to_owned()));
+    assert_eq!(skip("Some(Some(1)) 2"), Ok("2".to_owned()));
+    assert_eq!(skip("[1, [2], \"]\",] 2"), Ok("2".to_owned()));
+    assert_eq!(skip("{\"a\": (1, 2), 'b': {}} 2"), Ok("2".to_owned()));
+    assert_eq!(skip("Variant 2"), Ok("2".to_owned()));
+    assert_eq!(skip("Point /* ( */ (x: 1, y: [2, 3]) 2"), Ok("2".to_owned()));
+    assert_eq!(skip("Room(
+        name: \"(\", // )
+        doors: [(to: Some(\"Hall\"), locked: false)],
+        items: {\"key\": Key(id: 3, shape: '}')},
+    ) 2"), Ok("2".to_owned()));
+
+    let deep = format!("{}1{} 2", "[(".repeat(50), ",)]".repeat(50));
+    assert_eq!(skip(&deep), Ok("2".to_owned()));
+
+    assert_eq!(skip("(x: 340282366920938463463374607431768211455) 2"), Ok("2".to_owned()));
+    assert_eq!(skip("[-0xffffffffffffffffffffffffffffffffff, 7u8] 2"), Ok("2".to_owned()));
+    assert_eq!(skip("(\"x\": 1, 2) 2"), Ok("2".to_owned()));
+
+    assert_eq!(skip("[1, 2"), err(ParseError::ExpectedArrayEnd, 1, 6));
+    assert_eq!(skip("(1 2)"), err(ParseError::ExpectedStructEnd, 1, 4));
+    assert_eq!(skip("[1:2]"), err(ParseError::ExpectedArrayEnd, 1, 3));
+    assert_eq!(skip("(x: 1: 2)"), err(ParseError::ExpectedStructEnd, 1, 6));
+    assert_eq!(skip("(1: 2)"), err(ParseError::ExpectedStructEnd, 1, 3));
+    assert_eq!(skip("{1, 2}"), err(ParseError::ExpectedMapColon, 1, 3));
+    assert_eq!(skip("\"abc"), err(ParseError::ExpectedStringEnd, 1, 2));
+    assert_eq!(skip("}"), err(ParseError::ExpectedIdentifier, 1, 1));
+}
+
+#[derive(Debug, PartialEq)]
+struct ByteBuf(Vec<u8>);
+
+impl<'de> de::Deserialize<'de> for ByteBuf {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        struct ByteBufVisitor;
+
+        impl<'de> Visitor<'de> for ByteBufVisitor {
+            type Value = ByteBuf;
+
+            fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.write_str("bytes")
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> ::std::result::Result<ByteBuf, E> {
+                Ok(ByteBuf(v))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> ::std::result::Result<ByteBuf, A::Error>
+                where A: de::SeqAccess<'de>
+            {
+                let mut bytes = Vec::new();
+                while let Some(b) = seq.next_element()? {
+                    bytes.push(b);
+                }
+
+                Ok(ByteBuf(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+#[test]
+fn test_bytes() {
+    let hello = Ok(ByteBuf(b"Hello".to_vec()));
+
+    assert_eq!(hello, from_str("[72, 101, 108, 108, 111]"));
+    assert_eq!(hello, from_str("#![enable(bytes_as_base64)] [72, 101, 108, 108, 111]"));
+    assert_eq!(hello, from_str("#![enable(bytes_as_base64)] \"SGVsbG8=\""));
+    assert_eq!(hello, from_str("#![enable(bytes_as_base64)] \"SGVsbG8\""));
+    assert_eq!(hello, from_str("#![enable(bytes_as_hex)] [72, 101, 108, 108, 111]"));
+    assert_eq!(hello, from_str("#![enable(bytes_as_hex)] \"48656c6c6f\""));
+    assert_eq!(hello, from_str("#![enable(bytes_as_hex)] \"48656C6C6F\""));
+
+    // Valid as both base64 and hex, but the extension decides
+    assert_eq!(Ok(ByteBuf(vec![117, 230, 157, 109, 231, 159])),
+               from_str("#![enable(bytes_as_base64)] \"deadbeef\""));
+    assert_eq!(Ok(ByteBuf(vec![0xde, 0xad, 0xbe, 0xef])), from_str("#![enable(bytes_as_hex)] \"deadbeef\""));
+
+    assert_eq!(err(ParseError::ExpectedArray, 1, 1), from_str::<ByteBuf>("\"SGVsbG8=\""));
+    assert_eq!(err(ParseError::InvalidByteString, 1, 39),
+               from_str::<ByteBuf>("#![enable(bytes_as_base64)] \"SGVsbG8*\""));
+    assert_eq!(err(ParseError::InvalidByteString, 1, 36),
+               from_str::<ByteBuf>("#![enable(bytes_as_hex)] \"SGVsbG8=\""));
+    assert_eq!(err(ParseError::InvalidByteString, 1, 30),
+               from_str::<ByteBuf>("#![enable(bytes_as_hex)] \"+f\""));
+    assert_eq!(Err(Error::Message("the bytes_as_base64 and bytes_as_hex extensions can't be combined".to_owned())),
+               from_str::<ByteBuf>("#![enable(bytes_as_base64, bytes_as_hex)] \"00\""));
+    assert_eq!(hello, from_str("#![enable(bytes_as_base64, bytes_as_hex)] [72, 101, 108, 108, 111]"));
+}
+
+#[test]
+fn test_base64() {
+    use parse::decode_base64;
+
+    for &(encoded, decoded) in &[("", ""), ("Zg==", "f"), ("Zm8=", "fo"), ("Zm9v", "foo"),
+                                 ("Zm9vYg==", "foob"), ("Zm9vYmE=", "fooba"), ("Zm9vYmFy", "foobar"),
+                                 ("Zm9vYg", "foob"), ("+/+/", "\u{fb}\u{ff}\u{bf}")] {
+        let expected: Vec<u8> = decoded.chars().map(|c| c as u8).collect();
+        assert_eq!(decode_base64(encoded), Some(expected), "{}", encoded);
+    }
+
+    for &invalid in &["Z", "Zg=", "Zg===", "Zm9vY", "Zm=v", "Zm9v\n"] {
+        assert_eq!(decode_base64(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn test_integer_bounds() {
+    macro_rules! check_bounds {
+        ($($ty:ident)*) => {
+            $(
+                let (min, max) = ($ty::MIN, $ty::MAX);
+                assert_eq!(Ok(min), from_str::<$ty>(&min.to_string()));
+                assert_eq!(Ok(max), from_str::<$ty>(&max.to_string()));
+                assert_eq!(Ok(max), from_str::<$ty>(&format!("+{}", max)));
+
+                let bounds = |value: String| err(ParseError::IntegerOutOfBounds {
+                    value,
+                    ty: stringify!($ty),
+                    min: min as i128,
+                    max: max as i128,
+                }, 1, 1);
+                let above = max as i128 + 1;
+                let below = min as i128 - 1;
+                assert_eq!(bounds(above.to_string()), from_str::<$ty>(&above.to_string()));
+                assert_eq!(bounds(below.to_string()), from_str::<$ty>(&below.to_string()));
+            )*
+        };
+    }
+
+    check_bounds!(i8 i16 i32 i64 u8 u16 u32 u64);
+
+    assert_eq!(Ok(0u8), from_str("-0"));
+    assert_eq!(err(ParseError::ExpectedInteger, 1, 1), from_str::<i32>("-x"));
+    assert_eq!(err(ParseError::Eof, 1, 1), from_str::<i32>("+"));
+
+    let huge = "1".repeat(50);
+    assert_eq!(from_str::<u64>(&huge).unwrap_err().to_string(),
+               format!("1:1: integer {} overflows u64 (max 18446744073709551615)", huge));
+    assert_eq!(from_str::<u8>("300").unwrap_err().to_string(),
+               "1:1: integer 300 overflows u8 (max 255)");
+    assert_eq!(from_str::<u8>("-1").unwrap_err().to_string(),
+               "1:1: integer -1 underflows u8 (min 0)");
+}
+
+#[test]
+fn test_raw_string() {
+    assert_eq!(Ok("C:\\path".to_owned()), from_str("r\"C:\\path\""));
+    assert_eq!(Ok("a \"quoted\" word".to_owned()), from_str("r#\"a \"quoted\" word\"#"));
+    assert_eq!(Ok("\"#".to_owned()), from_str("r##\"\"#\"##"));
+    assert_eq!(Ok("two\nlines".to_owned()), from_str("r\"two\nlines\""));
+    assert_eq!(Ok(vec!["a".to_owned(), "b".to_owned()]), from_str("[r\"a\", \"b\"]"));
+
+    let value: ::value::Value = from_str("(r: r#\"raw\"#)").unwrap();
+    assert_eq!(value, from_str("(r: \"raw\")").unwrap());
+
+    assert_eq!(err(ParseError::ExpectedStringEnd, 1, 4), from_str::<String>("r#\"a\""));
+    assert_eq!(err(ParseError::ExpectedString, 1, 1), from_str::<String>("r#a"));
+}
+
+#[test]
+fn test_peek_type() {
+    for &(s, ref ty) in &[
+        ("true", RonType::Bool),
+        (" false ", RonType::Bool),
+        ("42", RonType::Integer),
+        ("-7 // negative", RonType::Integer),
+        ("1.5", RonType::Float),
+        ("1e3", RonType::Float),
+        ("'c'", RonType::Char),
+        ("\"s\"", RonType::String),
+        ("r#\"raw\"#", RonType::String),
+        ("Some(1)", RonType::Option),
+        ("None", RonType::Option),
+        ("()", RonType::Unit),
+        ("[1, 2]", RonType::Seq),
+        ("{1: 2}", RonType::Map),
+        ("(x: 1)", RonType::Struct(String::new())),
+        ("(1, 2)", RonType::Struct(String::new())),
+        ("MyStruct (x: 1)", RonType::Struct("MyStruct".to_string())),
+        ("B(true)", RonType::Struct("B".to_string())),
+        ("A", RonType::EnumVariant("A".to_string())),
+        ("Truthy", RonType::EnumVariant("Truthy".to_string())),
+        ("#![enable(implicit_some)] 'c'", RonType::Char),
+    ] {
+        assert_eq!(peek_type(s).as_ref(), Ok(ty), "{}", s);
+    }
+
+    assert_eq!(peek_type(""), err(ParseError::Eof, 1, 1));
+    assert_eq!(peek_type("  @"), err(ParseError::UnexpectedByte('@'), 1, 3));
+}
+
+#[test]
+fn test_any() {
+    use serde_json::Value as Json;
+
+    fn json(s: &str) -> Json {
+        from_str(s).unwrap()
+    }
+
+    fn expected(s: &str) -> Json {
+        serde_json::from_str(s).unwrap()
+    }
+
+    assert_eq!(json("true"), expected("true"));
+    assert_eq!(json("42"), expected("42"));
+    assert_eq!(json("-42"), expected("-42"));
+    assert_eq!(json("1.5"), expected("1.5"));
+    assert_eq!(json("1e2"), expected("100.0"));
+    assert_eq!(json("'c'"), expected("\"c\""));
+    assert_eq!(json("\"s\""), expected("\"s\""));
+    assert_eq!(json("[1, \"a\"]"), expected("[1, \"a\"]"));
+    assert_eq!(json("{\"a\": None}"), expected("{\"a\": null}"));
+    assert_eq!(json("Some(1)"), expected("1"));
+    assert_eq!(json("(1, 2.5)"), expected("[1, 2.5]"));
+    assert_eq!(json("Point(1, 2)"), expected("{\"Point\": [1, 2]}"));
+    assert_eq!(json("B(true)"), expected("{\"B\": true}"));
+    assert_eq!(json("(x: 1, \"y\": [])"), expected("{\"x\": 1, \"y\": []}"));
+    assert_eq!(json("Point (x: 1)"), expected("{\"x\": 1}"));
+    assert_eq!(json("A"), expected("\"A\""));
+    assert_eq!(json("()"), expected("null"));
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(untagged)]
+    enum Untagged {
+        Int(u8),
+        Float(f64),
+        Text(String),
+        Pair(bool, bool),
+        Named { name: String },
+    }
+
+    assert_eq!(Ok(vec![
+        Untagged::Int(1),
+        Untagged::Float(1.5),
+        Untagged::Text("a".to_string()),
+        Untagged::Pair(true, false),
+        Untagged::Named { name: "n".to_string() },
+    ]), from_str("[1, 1.5, \"a\", (true, false), (name: \"n\")]"));
+}
+
+#[test]
+fn test_type_annotations() {
+    assert_eq!(Ok(4u8), from_str("4u8"));
+    assert_eq!(Ok(-4i64), from_str("-4i64"));
+    assert_eq!(Ok(0.5f32), from_str("0.5f32"));
+    assert_eq!(Ok(1e3f64), from_str("1e3f64"));
+    assert_eq!(Ok(true), from_str("true:bool"));
+    assert_eq!(Ok(vec![(1u8, false)]), from_str("[(1u8, false:bool)]"));
+
+    assert_eq!(Ok(Value::Number(::value::Number::new(2.0))), from_str("2f32"));
+    assert_eq!(Ok(Value::Bool(false)), from_str("false:bool"));
+    assert_eq!(peek_type("2f32"), Ok(RonType::Float));
+    assert_eq!(peek_type("2u32"), Ok(RonType::Integer));
+
+    assert_eq!(err(ParseError::TrailingCharacters, 1, 2), from_str::<u8>("4u9"));
+    assert_eq!(err(ParseError::TrailingCharacters, 1, 5), from_str::<bool>("true:boolean"));
+
+    fn mismatched<T>(suffix: &'static str, expected: &'static str) -> Result<T> {
+        err(ParseError::MismatchedSuffix { suffix, expected }, 1, 1)
+    }
+    assert_eq!(mismatched("f32", "i32"), from_str::<i32>("5f32"));
+    assert_eq!(mismatched("i8", "f64"), from_str::<f64>("1.5i8"));
+    assert_eq!(mismatched("u64", "f32"), from_str::<f32>("2u64"));
+    assert_eq!(mismatched("f64", "f32"), from_str::<f32>("2f64"));
+    assert_eq!(mismatched("i64", "u8"), from_str::<u8>("5i64"));
+    assert_eq!(mismatched("u8", "u16"), from_str::<u16>("5u8"));
+    assert_eq!(mismatched("u32", "u8"), from_str::<u8>("300u32"));
+
+    fn out_of_bounds<T>(value: &str, ty: &'static str, min: i128, max: i128) -> Result<T> {
+        err(ParseError::IntegerOutOfBounds { value: value.to_string(), ty, min, max }, 1, 1)
+    }
+    assert_eq!(out_of_bounds("300", "u8", 0, 255), from_str::<u8>("300u8"));
+    assert_eq!(out_of_bounds("-1", "u16", 0, 65535), from_str::<u16>("-1u16"));
+
+    assert_eq!(Ok(Value::Number(::value::Number::new(300.0))), from_str("300u16"));
+    assert_eq!(Ok(Value::Number(::value::Number::new(-1.5))), from_str("-1.5f32"));
+}
+
+#[test]
+fn test_leading_zeros() {
+    fn parse<T>(s: &str, allow_leading_zeros: bool) -> Result<T>
+        where T: for<'a> serde::Deserialize<'a>
+    {
+        let config = DeserializerConfig { allow_leading_zeros, ..Default::default() };
+        Deserializer::from_str_with_config(s, config).into_serde()
+    }
+
+    assert_eq!(parse::<u32>("007", false), err(ParseError::LeadingZeros, 1, 1));
+    assert_eq!(parse::<i32>("-007", false), err(ParseError::LeadingZeros, 1, 1));
+    assert_eq!(parse::<f32>("00.7", false), err(ParseError::LeadingZeros, 1, 1));
+    assert_eq!(parse::<Value>("[1, 02]", false), err(ParseError::LeadingZeros, 1, 5));
+    assert_eq!(parse::<u32>("007", true), Ok(7));
+    assert_eq!(parse::<i32>("-007", true), Ok(-7));
+    assert_eq!(parse::<f32>("00.7", true), Ok(0.7));
+
+    for &allow in &[false, true] {
+        assert_eq!(parse::<u32>("0", allow), Ok(0));
+        assert_eq!(parse::<f32>("0.7", allow), Ok(0.7));
+        assert_eq!(parse::<u32>("0x0007", allow), Ok(7));
+        assert_eq!(parse::<i16>("-0x00ff", allow), Ok(-255));
+        assert_eq!(parse::<u8>("0xffu8", allow), Ok(255));
+        assert_eq!(parse::<Value>("0x0010", allow), Ok(Value::Number(::value::Number::new(16.0))));
+    }
+
+    assert_eq!(
+        ParseError::LeadingZeros.to_string(),
+        "integer literals must not have leading zeros"
+    );
+    assert_eq!(
+        parse::<u8>("0x100", false),
+        err(ParseError::IntegerOutOfBounds { value: "0x100".to_string(), ty: "u8", min: 0, max: 255 }, 1, 1)
+    );
+}
+
+#[test]
+fn test_ignored_any() {
+    use serde::de::IgnoredAny;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Known {
+        name: String,
+        id: u32,
+    }
+
+    let s = r##"Known(
+        a: 1, b: -2.5e3, c: "str", d: r#"raw"#, e: 'c',
+        f: [1, [2, (3, 4)]], g: {"k": Some(None)}, h: Variant(x: ()),
+        name: "known",
+        i: Point /* ( */ (x: 1), j: NaN,
+        id: 7,
+    )"##;
+    assert_eq!(from_str(s), Ok(Known { name: "known".to_owned(), id: 7 }));
+
+    assert_eq!(from_str("Room(doors: [(to: Some(\"Hall\"))], items: {'}': 3})"), Ok(IgnoredAny));
+    assert_eq!(from_str::<Vec<IgnoredAny>>("[1, \"a\", (b: [])]").map(|v| v.len()), Ok(3));
+    assert_eq!(from_str::<Known>("(name: \"n\", id: 1, extra: [1, 2)"),
+               err(ParseError::ExpectedArrayEnd, 1, 32));
+    assert_eq!(from_str("(big: 340282366920938463463374607431768211456, name: \"n\", id: 1)"),
+               Ok(Known { name: "n".to_owned(), id: 1 }));
+    assert_eq!(from_str::<Known>("(name: \"n\", id: 1, extra: [1: 2])"),
+               err(ParseError::ExpectedArrayEnd, 1, 29));
+}
@@ -1,14 +1,16 @@
-use std::collections::BTreeMap;
 use std::fmt;
 
-use serde::de::{Error, MapAccess, SeqAccess, Visitor};
+use serde::de::{DeserializeSeed, EnumAccess, Error, IntoDeserializer, MapAccess, SeqAccess,
+                 VariantAccess, Visitor};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
 use serde::{Deserialize, Deserializer};
 
 use de;
-use value::{Number, Value};
+use value::{Map, Number, Value};
 
 impl Value {
     /// Creates a value from a string reference.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> de::Result<Self> {
         Self::deserialize(&mut super::Deserializer::from_str(s))
     }
@@ -128,7 +130,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
         where A: MapAccess<'de>
     {
-        let mut res: BTreeMap<Value, Value> = BTreeMap::new();
+        let mut res = Map::new();
 
         while let Some(entry) = map.next_entry()? {
             res.insert(entry.0, entry.1);
@@ -138,14 +140,296 @@ impl<'de> Visitor<'de> for ValueVisitor {
     }
 }
 
+/// Deserializes Rust values from a `Value` tree.
+///
+/// Enum variants are represented like in JSON: a unit variant as a string
+/// with its name, any other variant as a map with a single entry from
+/// its name to its content. Tuple structs may be wrapped the same way, as
+/// `Value::from_str` reads `Point(1, 2)` like a variant.
+impl<'de> Deserializer<'de> for &'de Value {
+    type Error = de::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> de::Result<V::Value>
+        where V: Visitor<'de>
+    {
+        match *self {
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Char(c) => visitor.visit_char(c),
+            Value::Map(ref m) => {
+                let mut map = MapDeserializer::new(m.iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+
+                Ok(value)
+            }
+            Value::Number(ref n) => {
+                let f = n.get();
+                if f.fract() == 0.0 && f >= 0.0 && f < u64::MAX as f64 {
+                    visitor.visit_u64(f as u64)
+                } else if f.fract() == 0.0 && f >= i64::MIN as f64 && f < 0.0 {
+                    visitor.visit_i64(f as i64)
+                } else {
+                    visitor.visit_f64(f)
+                }
+            }
+            Value::Option(None) => visitor.visit_none(),
+            Value::Option(Some(ref v)) => visitor.visit_some(&**v),
+            Value::String(ref s) => visitor.visit_borrowed_str(s),
+            Value::Seq(ref s) => {
+                let mut seq = SeqDeserializer::new(s.iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+
+                Ok(value)
+            }
+            Value::Unit => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> de::Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> de::Result<V::Value>
+        where V: Visitor<'de>
+    {
+        match *self {
+            Value::Number(ref n) => visitor.visit_f64(n.get()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    /// Anything but `None` and `Some(...)` is taken as an implicit `Some`.
+    fn deserialize_option<V>(self, visitor: V) -> de::Result<V::Value>
+        where V: Visitor<'de>
+    {
+        match *self {
+            Value::Option(None) => visitor.visit_none(),
+            Value::Option(Some(ref v)) => visitor.visit_some(&**v),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> de::Result<V::Value>
+        where V: Visitor<'de>
+    {
+        visitor.visit_newtype_struct(self.unnamed(name))
+    }
+
+    fn deserialize_tuple_struct<V>(self, name: &'static str, _len: usize, visitor: V) -> de::Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.unnamed(name).deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V
+    ) -> de::Result<V::Value>
+        where V: Visitor<'de>
+    {
+        match *self {
+            Value::String(_) => visitor.visit_enum(ValueEnum { variant: self, content: None }),
+            Value::Map(ref m) if m.len() == 1 => {
+                let (variant, content) = m.iter().next().unwrap();
+                visitor.visit_enum(ValueEnum { variant, content: Some(content) })
+            }
+            _ => Err(de::Error::invalid_type(self.unexpected(), &"an enum variant")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string
+        bytes byte_buf unit unit_struct seq tuple
+        map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, de::Error> for &'de Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl Value {
+    /// Returns the content if this is a map with a single entry
+    /// from the tuple struct name `name`, and the value itself otherwise.
+    fn unnamed(&self, name: &str) -> &Self {
+        if let Value::Map(ref m) = *self {
+            if let Some((key, content)) = m.iter().next() {
+                match *key {
+                    Value::String(ref key) if m.len() == 1 && key == name => return content,
+                    _ => {}
+                }
+            }
+        }
+
+        self
+    }
+
+    /// The value's kind, for error messages.
+    fn unexpected(&self) -> ::serde::de::Unexpected<'_> {
+        use serde::de::Unexpected;
+
+        match *self {
+            Value::Bool(b) => Unexpected::Bool(b),
+            Value::Char(c) => Unexpected::Char(c),
+            Value::Map(_) => Unexpected::Map,
+            Value::Number(ref n) => Unexpected::Float(n.get()),
+            Value::Option(_) => Unexpected::Option,
+            Value::String(ref s) => Unexpected::Str(s),
+            Value::Seq(_) => Unexpected::Seq,
+            Value::Unit => Unexpected::Unit,
+        }
+    }
+}
+
+/// An enum variant, given by its name and its content
+/// unless it is a unit variant.
+struct ValueEnum<'de> {
+    variant: &'de Value,
+    content: Option<&'de Value>,
+}
+
+impl<'de> EnumAccess<'de> for ValueEnum<'de> {
+    type Error = de::Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> de::Result<(V::Value, Self)>
+        where V: DeserializeSeed<'de>
+    {
+        Ok((seed.deserialize(self.variant)?, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ValueEnum<'de> {
+    type Error = de::Error;
+
+    fn unit_variant(self) -> de::Result<()> {
+        match self.content {
+            None | Some(&Value::Unit) => Ok(()),
+            Some(v) => Err(de::Error::invalid_type(v.unexpected(), &"a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> de::Result<T::Value>
+        where T: DeserializeSeed<'de>
+    {
+        match self.content {
+            Some(v) => seed.deserialize(v),
+            None => Err(de::Error::invalid_type(self.variant.unexpected(), &"a newtype variant")),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> de::Result<V::Value>
+        where V: Visitor<'de>
+    {
+        match self.content {
+            Some(v) => v.deserialize_seq(visitor),
+            None => Err(de::Error::invalid_type(self.variant.unexpected(), &"a tuple variant")),
+        }
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> de::Result<V::Value>
+        where V: Visitor<'de>
+    {
+        match self.content {
+            Some(v) => v.deserialize_map(visitor),
+            None => Err(de::Error::invalid_type(self.variant.unexpected(), &"a struct variant")),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use super::*;
 
     fn eval(s: &str) -> Value {
         Value::from_str(s).expect("Failed to parse")
     }
 
+    #[test]
+    fn test_bool() {
+        assert_eq!(eval("true"), Value::Bool(true));
+        assert_eq!(de::from_str::<Value>("false"), Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn test_char() {
+        assert_eq!(eval("'c'"), Value::Char('c'));
+    }
+
+    #[test]
+    fn test_number() {
+        assert_eq!(eval("42"), Value::Number(Number::new(42.0)));
+        assert_eq!(eval("-1.5"), Value::Number(Number::new(-1.5)));
+    }
+
+    #[test]
+    fn test_string() {
+        assert_eq!(eval("\"Hello\""), Value::String("Hello".to_owned()));
+    }
+
+    #[test]
+    fn test_seq() {
+        assert_eq!(eval("[1, 2,]"), Value::Seq(vec![
+            Value::Number(Number::new(1.0)),
+            Value::Number(Number::new(2.0)),
+        ]));
+    }
+
+    #[test]
+    fn test_map() {
+        assert_eq!(eval("{\"a\": 'a', 'b': ()}"), Value::Map(vec![
+            (Value::String("a".to_owned()), Value::Char('a')),
+            (Value::Char('b'), Value::Unit),
+        ].into_iter().collect()));
+    }
+
+    #[test]
+    fn test_struct() {
+        let map = Value::Map(vec![
+            (Value::String("x".to_owned()), Value::Bool(true)),
+        ].into_iter().collect());
+
+        assert_eq!(eval("(x: true)"), map);
+        assert_eq!(eval("Named(x: true)"), map);
+    }
+
+    #[test]
+    fn test_tuple() {
+        let seq = Value::Seq(vec![Value::Number(Number::new(1.0)), Value::Bool(false)]);
+
+        assert_eq!(eval("(1, false)"), seq);
+
+        let named = Value::Map(vec![(Value::String("Named".to_owned()), seq)].into_iter().collect());
+        assert_eq!(eval("Named(1, false,)"), named);
+
+        let newtype = Value::Map(vec![
+            (Value::String("Named".to_owned()), Value::Bool(true)),
+        ].into_iter().collect());
+        assert_eq!(eval("Named(true)"), newtype);
+        assert_eq!(eval("Named ( true, )"), newtype);
+    }
+
+    #[test]
+    fn test_unit_variant() {
+        assert_eq!(eval("Variant"), Value::String("Variant".to_owned()));
+    }
+
+    #[test]
+    fn test_unit() {
+        assert_eq!(eval("()"), Value::Unit);
+    }
+
     #[test]
     fn test_none() {
         assert_eq!(eval("None"), Value::Option(None));
@@ -195,4 +479,112 @@ mod tests {
                        ]
                    )))));
     }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct MyStruct {
+        x: f32,
+        name: String,
+        tags: Vec<char>,
+        parent: Option<Box<MyStruct>>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum MyEnum {
+        A,
+        B(bool),
+        C(bool, i8),
+        D { a: i32 },
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Meters(u16);
+
+    fn map(entries: Vec<(&str, Value)>) -> Value {
+        Value::Map(Map::from_str_keys(entries))
+    }
+
+    fn num(n: f64) -> Value {
+        Value::Number(Number::new(n))
+    }
+
+    #[test]
+    fn test_from_value() {
+        assert_eq!(de::from_value(&Value::Bool(true)), Ok(true));
+        assert_eq!(de::from_value(&Value::Char('c')), Ok('c'));
+        assert_eq!(de::from_value(&num(-3.0)), Ok(-3i8));
+        assert_eq!(de::from_value(&num(3.0)), Ok(3.0f32));
+        assert_eq!(de::from_value(&num(2.5)), Ok(2.5f64));
+        assert_eq!(de::from_value(&Value::String("ron".to_owned())), Ok("ron".to_owned()));
+        assert_eq!(de::from_value(&Value::Unit), Ok(()));
+        assert_eq!(de::from_value(&Value::Option(None)), Ok(None::<bool>));
+        assert_eq!(de::from_value(&Value::Option(Some(Box::new(Value::Unit)))), Ok(Some(())));
+        assert_eq!(de::from_value(&num(1.0)), Ok(Some(1u8)));
+        assert_eq!(de::from_value(&num(42.0)), Ok(Meters(42)));
+        assert_eq!(de::from_value(&Value::Seq(vec![num(1.0), Value::Bool(false)])), Ok((1u32, false)));
+        assert_eq!(de::from_ron_value(&map(vec![("a", num(1.0))])),
+                   Ok(vec![("a".to_owned(), 1u64)].into_iter().collect::<BTreeMap<_, _>>()));
+
+        let v = map(vec![
+            ("x", num(1.5)),
+            ("name", Value::String("child".to_owned())),
+            ("tags", Value::Seq(vec![Value::Char('a')])),
+            ("parent", Value::Option(Some(Box::new(map(vec![
+                ("x", num(0.0)),
+                ("name", Value::String("root".to_owned())),
+                ("tags", Value::Seq(vec![])),
+                ("parent", Value::Option(None)),
+            ]))))),
+        ]);
+        assert_eq!(de::from_value(&v), Ok(MyStruct {
+            x: 1.5,
+            name: "child".to_owned(),
+            tags: vec!['a'],
+            parent: Some(Box::new(MyStruct { x: 0.0, name: "root".to_owned(), tags: vec![], parent: None })),
+        }));
+
+        assert_eq!(de::from_value(&Value::String("A".to_owned())), Ok(MyEnum::A));
+        assert_eq!(de::from_value(&map(vec![("B", Value::Bool(true))])), Ok(MyEnum::B(true)));
+        assert_eq!(de::from_value(&map(vec![("C", Value::Seq(vec![Value::Bool(false), num(-1.0)]))])),
+                   Ok(MyEnum::C(false, -1)));
+        assert_eq!(de::from_value(&map(vec![("D", map(vec![("a", num(7.0))]))])), Ok(MyEnum::D { a: 7 }));
+    }
+
+    #[test]
+    fn test_from_value_errors() {
+        assert!(de::from_value::<u8>(&num(1.5)).is_err());
+        assert!(de::from_value::<u8>(&num(256.0)).is_err());
+        assert!(de::from_value::<bool>(&Value::Unit).is_err());
+        assert!(de::from_value::<(u8, u8)>(&Value::Seq(vec![num(1.0)])).is_err());
+        assert!(de::from_value::<MyEnum>(&Value::String("E".to_owned())).is_err());
+        assert!(de::from_value::<MyEnum>(&Value::String("B".to_owned())).is_err());
+        assert!(de::from_value::<MyEnum>(&map(vec![("A", num(1.0)), ("B", Value::Bool(true))])).is_err());
+    }
+
+    #[test]
+    fn test_from_value_roundtrip() {
+        let s = MyStruct {
+            x: -0.25,
+            name: "a \"quoted\" name".to_owned(),
+            tags: vec!['x', '\n'],
+            parent: None,
+        };
+        let v = eval(&::ser::to_string(&s).unwrap());
+        assert_eq!(de::from_value(&v), Ok(s));
+
+        let v = eval(&::ser::to_string(&vec![Some(1u64 << 40), None]).unwrap());
+        assert_eq!(de::from_value(&v), Ok(vec![Some(1u64 << 40), None]));
+
+        let v = eval("[A, B(true), C(false, -3)]");
+        assert_eq!(de::from_value(&v), Ok(vec![MyEnum::A, MyEnum::B(true), MyEnum::C(false, -3)]));
+
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct Meters(f32);
+
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct Pair(u8, char);
+
+        let s = (Meters(1.5), Pair(2, 'x'));
+        let v = eval(&::ser::to_string_with_struct_names(&s).unwrap());
+        assert_eq!(de::from_value(&v), Ok(s));
+    }
 }
@@ -0,0 +1,116 @@
+//! RON extensions, which change how certain types are represented.
+//!
+//! The serializer uses the extensions set in its `PrettyConfig` and
+//! announces them with `#![enable(...)]` attributes at the start of the
+//! output; the deserializer enables the ones named in such attributes.
+
+use std::fmt::{Formatter, Result as FmtResult};
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+bitflags! {
+    /// A set of RON extensions.
+    pub struct Extensions: usize {
+        /// Serialize `Some(value)` as just `value`.
+        const IMPLICIT_SOME = 0x1;
+        /// Serialize newtype structs as their inner value, without
+        /// the struct name and parentheses.
+        const UNWRAP_NEWTYPES = 0x2;
+        /// Serialize a newtype variant wrapping a struct or tuple as
+        /// `Variant(fields...)` instead of `Variant((fields...))`.
+        const UNWRAP_VARIANT_NEWTYPES = 0x4;
+        /// Accept base64 strings in place of sequences of bytes when
+        /// deserializing byte buffers. The serializer always writes sequences.
+        const BYTES_AS_BASE64 = 0x8;
+        /// Like `BYTES_AS_BASE64`, but for strings of hex digits.
+        ///
+        /// Many base64 strings are valid hex as well, so the two extensions
+        /// can't be combined; byte strings are rejected if both are enabled.
+        const BYTES_AS_HEX = 0x10;
+    }
+}
+
+/// The names of the extensions, as used in `#![enable(...)]` attributes.
+const NAMES: &[(&str, Extensions)] = &[
+    ("implicit_some", Extensions::IMPLICIT_SOME),
+    ("unwrap_newtypes", Extensions::UNWRAP_NEWTYPES),
+    ("unwrap_variant_newtypes", Extensions::UNWRAP_VARIANT_NEWTYPES),
+    ("bytes_as_base64", Extensions::BYTES_AS_BASE64),
+    ("bytes_as_hex", Extensions::BYTES_AS_HEX),
+];
+
+impl Extensions {
+    /// Returns the extension named `ident`, as used in
+    /// `#![enable(...)]` attributes.
+    pub fn from_ident(ident: &[u8]) -> Option<Extensions> {
+        NAMES.iter()
+            .find(|&&(name, _)| name.as_bytes() == ident)
+            .map(|&(_, ext)| ext)
+    }
+
+    /// Returns the names of all extensions contained in `self`.
+    pub fn idents(&self) -> Vec<&'static str> {
+        NAMES.iter()
+            .filter(|&&(_, ext)| self.contains(ext))
+            .map(|&(name, _)| name)
+            .collect()
+    }
+}
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Extensions::empty()
+    }
+}
+
+/// Serializes the set as a sequence of extension names,
+/// e.g. `["implicit_some", "unwrap_newtypes"]`.
+impl Serialize for Extensions {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let idents = self.idents();
+        let mut seq = serializer.serialize_seq(Some(idents.len()))?;
+        for ident in idents {
+            seq.serialize_element(ident)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Extensions {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        deserializer.deserialize_seq(ExtensionsVisitor)
+    }
+}
+
+struct ExtensionsVisitor;
+
+impl<'de> Visitor<'de> for ExtensionsVisitor {
+    type Value = Extensions;
+
+    fn expecting(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str("a sequence of RON extension names")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Extensions, A::Error>
+        where A: SeqAccess<'de>
+    {
+        let mut extensions = Extensions::empty();
+        while let Some(ident) = seq.next_element::<String>()? {
+            match Extensions::from_ident(ident.as_bytes()) {
+                Some(ext) => extensions |= ext,
+                None => return Err(de::Error::custom(
+                    format_args!("unknown extension `{}`", ident)
+                )),
+            }
+        }
+
+        Ok(extensions)
+    }
+}
@@ -55,13 +55,49 @@ ron = "*"
 
 Serializing / Deserializing is as simple as calling `to_string` / `from_str`.
 
+## `no_std`
+
+Without the default `std` feature, the crate is `no_std` and only needs
+`alloc`. Only the serializer and the extensions are available then.
+
 !*/
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[macro_use]
+extern crate bitflags;
 #[macro_use]
 extern crate serde;
+#[cfg(any(test, feature = "json"))]
+#[cfg_attr(all(test, feature = "json"), macro_use)]
+extern crate serde_json;
 
+#[cfg(feature = "std")]
 pub mod de;
+pub mod extensions;
+#[cfg(feature = "std")]
+pub mod patch;
 pub mod ser;
+#[cfg(feature = "std")]
 pub mod value;
 
+#[cfg(feature = "std")]
 mod parse;
+
+/// Stands in for `std` without the `std` feature, so that modules can
+/// keep importing from `std`.
+#[cfg(not(feature = "std"))]
+mod std {
+    pub use core::*;
+    pub use alloc::collections;
+
+    /// The items of the `std` prelude which are not in `core`'s.
+    pub mod prelude {
+        pub use alloc::borrow::ToOwned;
+        pub use alloc::string::{String, ToString};
+        pub use alloc::vec::Vec;
+    }
+}
@@ -1,14 +1,19 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
-use std::ops::Neg;
 use std::str::{FromStr, from_utf8, from_utf8_unchecked};
 
 use de::{Error, ParseError, Result};
+use extensions::Extensions;
 
 const DIGITS: &[u8] = b"0123456789";
 const FLOAT_CHARS: &[u8] = b"0123456789.+-eE";
+const HEX_DIGITS: &[u8] = b"0123456789abcdefABCDEF";
 const IDENT_FIRST: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_";
 const IDENT_CHAR: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_0123456789";
 const WHITE_SPACE: &[u8] = b"\n\t\r ";
+/// The type suffixes numbers may be annotated with
+const NUMBER_SUFFIXES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "u8", "u16", "u32", "u64", "u128", "f32", "f64",
+];
 
 #[derive(Clone, Copy, Debug)]
 pub struct Bytes<'a> {
@@ -25,7 +30,8 @@ impl<'a> Bytes<'a> {
             line: 1,
         };
 
-        b.skip_ws();
+        // An unclosed block comment is reported once parsing starts
+        let _ = b.skip_ws();
 
         b
     }
@@ -52,17 +58,86 @@ impl<'a> Bytes<'a> {
     }
 
     pub fn bool(&mut self) -> Result<bool> {
-        if self.consume("true") {
-            Ok(true)
+        let b = if self.consume("true") {
+            true
         } else if self.consume("false") {
-            Ok(false)
+            false
         } else {
-            self.err(ParseError::ExpectedBoolean)
+            return self.err(ParseError::ExpectedBoolean);
+        };
+
+        self.bool_annotation();
+
+        Ok(b)
+    }
+
+    /// Consumes the `:bool` type annotation following a boolean, if any.
+    pub fn bool_annotation(&mut self) {
+        if self.test_for(":bool") && !self.check_ident_char(":bool".len()) {
+            let _ = self.advance(":bool".len());
+        }
+    }
+
+    /// Returns whether the number starting here is a float, having
+    /// a fractional part, an exponent or a float type suffix.
+    pub fn check_float(&self) -> bool {
+        if self.check_hex() {
+            return false;
         }
+
+        let len = self.next_bytes_contained_in(FLOAT_CHARS);
+        let rest = &self.bytes[len..];
+
+        self.bytes[..len].iter().any(|b| b".eE".contains(b)) ||
+            rest.starts_with(b"f32") || rest.starts_with(b"f64")
+    }
+
+    /// Returns whether the number starting here is hexadecimal, like `0xff`.
+    pub fn check_hex(&self) -> bool {
+        self.bytes[self.sign_len()..].starts_with(b"0x")
+    }
+
+    /// Returns whether the number starting here has superfluous leading
+    /// zeros, like `007` or `00.7`. Zeros after a `0x` prefix are fine.
+    pub fn check_leading_zeros(&self) -> bool {
+        let digits = &self.bytes[self.sign_len()..];
+
+        digits.first() == Some(&b'0') && digits.get(1).is_some_and(|b| DIGITS.contains(b))
+    }
+
+    fn sign_len(&self) -> usize {
+        match self.peek() {
+            Some(b'+') | Some(b'-') => 1,
+            _ => 0,
+        }
+    }
+
+    /// Returns the type suffix of the number starting here, if any,
+    /// without consuming anything.
+    pub fn peek_number_suffix(&self) -> Option<&'static str> {
+        let num_bytes = if self.check_float() {
+            self.next_bytes_contained_in(FLOAT_CHARS)
+        } else {
+            let (start, _, num_bytes) = self.integer_digits().ok()?;
+            start + num_bytes
+        };
+
+        let mut rest = *self;
+        let _ = rest.advance(num_bytes);
+        rest.number_suffix()
+    }
+
+    /// Consumes the type suffix following a number, like the `u8` of `4u8`,
+    /// if any, and returns it.
+    pub fn number_suffix(&mut self) -> Option<&'static str> {
+        let suffix = *NUMBER_SUFFIXES.iter().find(|suffix| self.check_ident(suffix))?;
+        let _ = self.advance(suffix.len());
+
+        Some(suffix)
     }
 
     pub fn bytes(&self) -> &[u8] {
-        &self.bytes
+        self.bytes
     }
 
     pub fn char(&mut self) -> Result<char> {
@@ -70,32 +145,101 @@ impl<'a> Bytes<'a> {
             return self.err(ParseError::ExpectedChar);
         }
 
-        let c = self.eat_byte()?;
-
-        let c = if c == b'\\' {
-            let c = self.eat_byte()?;
-
-            if c != b'\\' && c != b'\'' {
-                return self.err(ParseError::InvalidEscape);
-            }
-
-            c
+        let c = if self.consume("\\") {
+            self.parse_char_escape()?
         } else {
-            c
+            match self.utf8_char()? {
+                '\'' => return self.err(ParseError::ExpectedChar),
+                c => c,
+            }
         };
 
         if !self.consume("'") {
             return self.err(ParseError::ExpectedChar);
         }
 
-        Ok(c as char)
+        Ok(c)
     }
 
-    pub fn comma(&mut self) -> bool {
-        self.skip_ws();
+    /// Consumes a single UTF-8 encoded char.
+    fn utf8_char(&mut self) -> Result<char> {
+        let len = self.bytes.len().min(4);
+        let s = match from_utf8(&self.bytes[..len]) {
+            Ok(s) => s,
+            Err(e) if e.valid_up_to() > 0 => unsafe {
+                from_utf8_unchecked(&self.bytes[..e.valid_up_to()])
+            },
+            Err(e) => return self.err(e.into()),
+        };
+
+        let c = s.chars().next().ok_or_else(|| self.error(ParseError::Eof))?;
+        let _ = self.advance(c.len_utf8());
+
+        Ok(c)
+    }
 
-        if self.consume(",") {
-            self.skip_ws();
+    /// Parses the escape sequence of a char literal, following
+    /// the backslash.
+    fn parse_char_escape(&mut self) -> Result<char> {
+        let c = match self.eat_byte()? {
+            b'\'' => '\'',
+            b'"' => '"',
+            b'\\' => '\\',
+            b'0' => '\0',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'x' => {
+                if self.next_bytes_contained_in(HEX_DIGITS) < 2 {
+                    return self.err(ParseError::InvalidEscape);
+                }
+
+                match self.hex_number(2)? {
+                    n @ 0..=0x7F => n as u8 as char,
+                    _ => return self.err(ParseError::InvalidEscape),
+                }
+            }
+            b'u' => {
+                if !self.consume("{") {
+                    return self.err(ParseError::InvalidEscape);
+                }
+
+                let digits = self.next_bytes_contained_in(HEX_DIGITS);
+                if digits == 0 || digits > 6 {
+                    return self.err(ParseError::InvalidEscape);
+                }
+
+                let n = self.hex_number(digits)?;
+
+                if !self.consume("}") {
+                    return self.err(ParseError::InvalidEscape);
+                }
+
+                match ::std::char::from_u32(n) {
+                    Some(c) => c,
+                    None => return self.err(ParseError::InvalidEscape),
+                }
+            }
+            _ => return self.err(ParseError::InvalidEscape),
+        };
+
+        Ok(c)
+    }
+
+    /// Consumes `digits` hex digits, which have to be present.
+    fn hex_number(&mut self, digits: usize) -> Result<u32> {
+        let s = unsafe { from_utf8_unchecked(&self.bytes[..digits]) };
+        let n = u32::from_str_radix(s, 16).map_err(|_| self.error(ParseError::InvalidEscape))?;
+
+        let _ = self.advance(digits);
+
+        Ok(n)
+    }
+
+    pub fn comma(&mut self) -> bool {
+        if self.skip_ws().is_ok() && self.consume(",") {
+            // An unclosed block comment is reported by the next `skip_ws`
+            let _ = self.skip_ws();
 
             true
         } else {
@@ -150,20 +294,85 @@ impl<'a> Bytes<'a> {
         Error::Parser(kind, Position { line: self.line, col: self.column })
     }
 
+    /// Consumes all `#![enable(...)]` attributes at the current position
+    /// and returns the extensions they enable.
+    ///
+    /// An invalid attribute is left in place, so parsing the value
+    /// afterwards reports an error at its position.
+    pub fn extensions(&mut self) -> Extensions {
+        let mut exts = Extensions::empty();
+
+        loop {
+            let backup = *self;
+
+            match self.extension_attribute() {
+                Some(attribute) => exts |= attribute,
+                None => {
+                    *self = backup;
+
+                    break exts;
+                }
+            }
+
+            if self.skip_ws().is_err() {
+                break exts;
+            }
+        }
+    }
+
+    fn extension_attribute(&mut self) -> Option<Extensions> {
+        let mut exts = Extensions::empty();
+
+        for token in &["#", "!", "[", "enable", "("] {
+            self.skip_ws().ok()?;
+            if !self.consume(token) {
+                return None;
+            }
+        }
+
+        loop {
+            self.skip_ws().ok()?;
+            if self.peek() == Some(b')') {
+                break;
+            }
+
+            let ident = self.identifier().ok()?;
+            exts |= Extensions::from_ident(ident)?;
+
+            if !self.comma() {
+                break;
+            }
+        }
+
+        for token in &[")", "]"] {
+            self.skip_ws().ok()?;
+            if !self.consume(token) {
+                return None;
+            }
+        }
+
+        Some(exts)
+    }
+
     pub fn float<T>(&mut self) -> Result<T>
-        where T: FromStr
+        where T: Float
     {
         let num_bytes = self.next_bytes_contained_in(FLOAT_CHARS);
 
         let s = unsafe { from_utf8_unchecked(&self.bytes[0..num_bytes]) };
-        let res = FromStr::from_str(s).map_err(|_| self.error(ParseError::ExpectedFloat));
+        let res = FromStr::from_str(s).map_err(|_| self.error(ParseError::ExpectedFloat))?;
 
-        let _ = self.advance(num_bytes);
+        let mut rest = *self;
+        let _ = rest.advance(num_bytes);
+        if let Some(suffix) = rest.number_suffix().filter(|&suffix| suffix != T::NAME) {
+            return self.err(ParseError::MismatchedSuffix { suffix, expected: T::NAME });
+        }
+        *self = rest;
 
-        res
+        Ok(res)
     }
 
-    pub fn identifier(&mut self) -> Result<&[u8]> {
+    pub fn identifier(&mut self) -> Result<&'a [u8]> {
         if IDENT_FIRST.contains(&self.peek_or_eof()?) {
             let bytes = self.next_bytes_contained_in(IDENT_CHAR);
 
@@ -183,43 +392,114 @@ impl<'a> Bytes<'a> {
             .fold(0, |acc, _| acc + 1)
     }
 
-    pub fn skip_ws(&mut self) {
+    /// Skips whitespace and comments.
+    ///
+    /// A block comment without an end is left in place, returning
+    /// an `UnclosedBlockComment` error.
+    pub fn skip_ws(&mut self) -> Result<()> {
         while self.peek().map(|c| WHITE_SPACE.contains(&c)).unwrap_or(false) {
             let _ = self.advance_single();
         }
 
-        if self.skip_comment() {
-            self.skip_ws();
+        if self.skip_comment()? {
+            self.skip_ws()?;
         }
+
+        Ok(())
     }
 
     pub fn peek(&self) -> Option<u8> {
-        self.bytes.get(0).map(|b| *b)
+        self.bytes.first().copied()
     }
 
     pub fn peek_or_eof(&self) -> Result<u8> {
-        self.bytes.get(0).map(|b| *b).ok_or(self.error(ParseError::Eof))
+        self.bytes.first().copied().ok_or(self.error(ParseError::Eof))
     }
 
-    pub fn signed_integer<T>(&mut self) -> Result<T>
-        where T: FromStr + Neg<Output=T>
+    /// Parses an optionally signed integer, checking that it fits into `T`.
+    ///
+    /// The digits may be hexadecimal if prefixed with `0x`, like in `-0xff`.
+    pub fn integer<T>(&mut self) -> Result<T>
+        where T: Integer
     {
-        match self.peek_or_eof()? {
-            b'+' => {
-                let _ = self.advance_single();
+        let negative = self.peek_or_eof()? == b'-';
+        let (start, radix, num_bytes) = self.integer_digits()?;
+
+        // Digits are ASCII
+        let digits = unsafe { from_utf8_unchecked(&self.bytes[start..start + num_bytes]) };
+        let value = i128::from_str_radix(digits, radix)
+            .ok()
+            .map(|v| if negative { -v } else { v });
+
+        let mut rest = *self;
+        let _ = rest.advance(start + num_bytes);
+        if let Some(suffix) = rest.number_suffix().filter(|&suffix| suffix != T::NAME) {
+            return self.err(ParseError::MismatchedSuffix { suffix, expected: T::NAME });
+        }
 
-                self.unsigned_integer()
-            }
-            b'-' => {
-                let _ = self.advance_single();
+        match value.filter(|v| (T::MIN..=T::MAX).contains(v)) {
+            Some(v) => {
+                *self = rest;
 
-                self.unsigned_integer::<T>().map(Neg::neg)
+                Ok(T::from_i128(v))
             }
-            _ => self.unsigned_integer(),
+            None => self.err(ParseError::IntegerOutOfBounds {
+                value: format!(
+                    "{}{}{}",
+                    if negative { "-" } else { "" },
+                    if radix == 16 { "0x" } else { "" },
+                    digits,
+                ),
+                ty: T::NAME,
+                min: T::MIN,
+                max: T::MAX,
+            }),
+        }
+    }
+
+    /// Returns the number of `#`s of the raw string literal
+    /// starting here, if there is one.
+    fn raw_string_hashes(&self) -> Option<usize> {
+        if self.peek() != Some(b'r') {
+            return None;
+        }
+
+        let hashes = self.bytes[1..].iter().take_while(|&&b| b == b'#').count();
+
+        match self.bytes.get(1 + hashes) {
+            Some(&b'"') => Some(hashes),
+            _ => None,
         }
     }
 
-    pub fn string(&mut self) -> Result<ParsedStr> {
+    pub fn check_raw_string(&self) -> bool {
+        self.raw_string_hashes().is_some()
+    }
+
+    /// Parses a raw string literal like `r#"a "quoted" word"#`.
+    fn raw_string(&mut self, hashes: usize) -> Result<&'a str> {
+        let _ = self.advance(hashes + 2);
+
+        let bytes = self.bytes;
+        let len = bytes
+            .iter()
+            .enumerate()
+            .position(|(i, &b)| {
+                b == b'"' && bytes.len() > i + hashes && bytes[i + 1..=i + hashes].iter().all(|&b| b == b'#')
+            })
+            .ok_or(self.error(ParseError::ExpectedStringEnd))?;
+
+        let s = from_utf8(&bytes[..len]).map_err(|e| self.error(e.into()))?;
+        let _ = self.advance(len + 1 + hashes);
+
+        Ok(s)
+    }
+
+    pub fn string(&mut self) -> Result<ParsedStr<'a>> {
+        if let Some(hashes) = self.raw_string_hashes() {
+            return self.raw_string(hashes).map(ParsedStr::Slice);
+        }
+
         if !self.consume("\"") {
             return self.err(ParseError::ExpectedString);
         }
@@ -231,7 +511,8 @@ impl<'a> Bytes<'a> {
             .ok_or(self.error(ParseError::ExpectedStringEnd))?;
 
         if *end_or_escape == b'"' {
-            let s = from_utf8(&self.bytes[..i]).map_err(|e| self.error(e.into()))?;
+            let bytes = self.bytes;
+            let s = from_utf8(&bytes[..i]).map_err(|e| self.error(e.into()))?;
 
             // Advance by the number of bytes of the string
             // + 1 for the `"`.
@@ -266,30 +547,89 @@ impl<'a> Bytes<'a> {
         }
     }
 
-    fn test_for(&self, s: &str) -> bool {
-        s.bytes().enumerate().all(|(i, b)| self.bytes.get(i).map(|t| *t == b).unwrap_or(false))
+    /// Consumes a struct field's name and the colon following it,
+    /// like `x:` or `"x":`, returning whether there was one.
+    /// Otherwise, nothing is consumed.
+    pub fn field_name(&mut self) -> bool {
+        let mut bytes = *self;
+        let name = match bytes.peek() {
+            Some(b'"') => bytes.skip_string(),
+            _ => bytes.identifier().map(|_| ()),
+        };
+
+        let found = name.is_ok() && bytes.skip_ws().is_ok() && bytes.consume(":");
+        if found {
+            *self = bytes;
+        }
+
+        found
+    }
+
+    /// Consumes an integer like `integer` does, including its type
+    /// suffix, but without converting it or checking its range.
+    pub fn skip_integer(&mut self) -> Result<()> {
+        let (start, _, num_bytes) = self.integer_digits()?;
+
+        let _ = self.advance(start + num_bytes);
+        self.number_suffix();
+
+        Ok(())
     }
 
-    pub fn unsigned_integer<T>(&mut self) -> Result<T> where T: FromStr {
-        let num_bytes = self.next_bytes_contained_in(DIGITS);
+    /// Finds the digits of the integer starting here, returning where they
+    /// start after the sign and `0x` prefix, their radix and their number.
+    fn integer_digits(&self) -> Result<(usize, u32, usize)> {
+        let sign = self.sign_len();
+        let (prefix, radix, allowed) = if self.bytes[sign..].starts_with(b"0x") {
+            (2, 16, HEX_DIGITS)
+        } else {
+            (0, 10, DIGITS)
+        };
+        let start = sign + prefix;
+
+        let num_bytes = self.bytes[start..]
+            .iter()
+            .take_while(|b| allowed.contains(b))
+            .count();
 
         if num_bytes == 0 {
-            return self.err(ParseError::Eof);
+            return match self.bytes.get(start) {
+                Some(_) => self.err(ParseError::ExpectedInteger),
+                None => self.err(ParseError::Eof),
+            };
         }
 
-        let res = FromStr::from_str(unsafe { from_utf8_unchecked(&self.bytes[0..num_bytes]) })
-            .map_err(|_| self.error(ParseError::ExpectedInteger));
+        Ok((start, radix, num_bytes))
+    }
+
+    /// Consumes a string without decoding it.
+    pub fn skip_string(&mut self) -> Result<()> {
+        if !self.consume("\"") {
+            return self.err(ParseError::ExpectedString);
+        }
+
+        let mut escaped = false;
+        let len = self.bytes
+            .iter()
+            .position(|&b| match b {
+                _ if escaped => { escaped = false; false }
+                b'\\' => { escaped = true; false }
+                b => b == b'"',
+            })
+            .ok_or(self.error(ParseError::ExpectedStringEnd))?;
 
-        let _ = self.advance(num_bytes);
+        self.advance(len + 1)
+    }
 
-        res
+    fn test_for(&self, s: &str) -> bool {
+        s.bytes().enumerate().all(|(i, b)| self.bytes.get(i).map(|t| *t == b).unwrap_or(false))
     }
 
     fn decode_hex_escape(&mut self) -> Result<u16> {
         let mut n = 0;
         for _ in 0..4 {
             n = match self.eat_byte()? {
-                c @ b'0' ... b'9' => n * 16_u16 + ((c as u16) - (b'0' as u16)),
+                c @ b'0' ..= b'9' => n * 16_u16 + ((c as u16) - (b'0' as u16)),
                 b'a' | b'A' => n * 16_u16 + 10_u16,
                 b'b' | b'B' => n * 16_u16 + 11_u16,
                 b'c' | b'C' => n * 16_u16 + 12_u16,
@@ -318,11 +658,11 @@ impl<'a> Bytes<'a> {
             b't' => store.push(b'\t'),
             b'u' => {
                 let c: char = match self.decode_hex_escape()? {
-                    0xDC00 ... 0xDFFF => {
+                    0xDC00 ..= 0xDFFF => {
                         return self.err(ParseError::InvalidEscape);
                     }
 
-                    n1 @ 0xD800 ... 0xDBFF => {
+                    n1 @ 0xD800 ..= 0xDBFF => {
                         if self.eat_byte()? != b'\\' {
                             return self.err(ParseError::InvalidEscape);
                         }
@@ -333,13 +673,13 @@ impl<'a> Bytes<'a> {
 
                         let n2 = self.decode_hex_escape()?;
 
-                        if n2 < 0xDC00 || n2 > 0xDFFF {
+                        if !(0xDC00..=0xDFFF).contains(&n2) {
                             return self.err(ParseError::InvalidEscape);
                         }
 
                         let n = (((n1 - 0xD800) as u32) << 10 | (n2 - 0xDC00) as u32) + 0x1_0000;
 
-                        match ::std::char::from_u32(n as u32) {
+                        match ::std::char::from_u32(n) {
                             Some(c) => c,
                             None => {
                                 return self.err(ParseError::InvalidEscape);
@@ -369,26 +709,137 @@ impl<'a> Bytes<'a> {
         Ok(())
     }
 
-    fn skip_comment(&mut self) -> bool {
+    /// Skips a line comment, or a block comment like `/* a /* b */ c */`,
+    /// which may be nested like in Rust. Returns whether there was one.
+    fn skip_comment(&mut self) -> Result<bool> {
         if self.consume("//") {
             let bytes = self.bytes.iter().take_while(|&&b| b != b'\n').count();
 
             let _ = self.advance(bytes);
 
-            true
+            Ok(true)
+        } else if self.test_for("/*") {
+            let mut depth = 0;
+            let mut i = 0;
+            while i + 1 < self.bytes.len() {
+                match &self.bytes[i..i + 2] {
+                    b"/*" => depth += 1,
+                    b"*/" => depth -= 1,
+                    _ => {
+                        i += 1;
+                        continue;
+                    }
+                }
+                i += 2;
+
+                if depth == 0 {
+                    let _ = self.advance(i);
+
+                    return Ok(true);
+                }
+            }
+
+            self.err(ParseError::UnclosedBlockComment)
         } else {
-            false
+            Ok(false)
         }
     }
 }
 
+/// An integer type the parser can produce.
+pub trait Integer: Sized {
+    const NAME: &'static str;
+    const MIN: i128;
+    const MAX: i128;
+
+    /// Converts a value known to be in `MIN..=MAX`.
+    fn from_i128(v: i128) -> Self;
+}
+
+macro_rules! impl_integer {
+    ($($ty:ident)*) => {
+        $(
+            impl Integer for $ty {
+                const NAME: &'static str = stringify!($ty);
+                const MIN: i128 = $ty::MIN as i128;
+                const MAX: i128 = $ty::MAX as i128;
+
+                fn from_i128(v: i128) -> Self {
+                    v as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8 i16 i32 i64 i128 u8 u16 u32 u64);
+
+/// A float type the parser can produce.
+pub trait Float: FromStr {
+    const NAME: &'static str;
+}
+
+impl Float for f32 {
+    const NAME: &'static str = "f32";
+}
+
+impl Float for f64 {
+    const NAME: &'static str = "f64";
+}
+
+/// Decodes a string of an even number of hex digits.
+pub fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    // `from_str_radix` would also take a sign, like in `+f`
+    if s.len() % 2 != 0 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Decodes standard base64, with or without padding.
+pub fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    fn sextet(b: u8) -> Option<u32> {
+        let v = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+
+        Some(u32::from(v))
+    }
+
+    let data = s.trim_end_matches('=');
+    if s.len() - data.len() > 2 || (data.len() != s.len() && s.len() % 4 != 0) || data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.as_bytes().chunks(4) {
+        let bits = chunk
+            .iter()
+            .try_fold(0, |acc, &b| sextet(b).map(|v| acc << 6 | v))?
+            << (6 * (4 - chunk.len()));
+
+        bytes.extend_from_slice(&[(bits >> 16) as u8, (bits >> 8) as u8, bits as u8][..chunk.len() - 1]);
+    }
+
+    Some(bytes)
+}
+
 #[derive(Clone, Debug)]
 pub enum ParsedStr<'a> {
     Allocated(String),
     Slice(&'a str),
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Position {
     pub col: usize,
     pub line: usize,
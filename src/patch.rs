@@ -0,0 +1,406 @@
+//! Structural changes to `Value` trees, for example to migrate configurations.
+//!
+//! Paths point into a value like `/server/ports/0`: every segment is either
+//! a string key of a map (or struct) or an index into a sequence.
+//! The empty path is the root value.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use value::{Map, Value};
+
+/// Patch result.
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// An error applying a patch, carrying the offending path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The path is neither empty nor starts with `/`,
+    /// or the operation can't be applied to the root
+    InvalidPath(String),
+    /// There is no value at the path
+    NotFound(String),
+    /// The value at the path is not of the kind the operation needs
+    WrongType(String),
+    /// Renaming would overwrite another entry
+    KeyExists(String),
+    /// The range of a splice exceeds the sequence
+    OutOfRange(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidPath(ref p) => write!(f, "Invalid path `{}`", p),
+            Error::NotFound(ref p) => write!(f, "No value at `{}`", p),
+            Error::WrongType(ref p) => write!(f, "Value at `{}` has the wrong type", p),
+            Error::KeyExists(ref p) => write!(f, "A value at `{}` already exists", p),
+            Error::OutOfRange(ref p) => write!(f, "Range out of bounds at `{}`", p),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+/// A single change to a `Value` tree.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Patch {
+    /// Sets the value at the path, adding a new map entry if needed.
+    Set(String, Value),
+    /// Removes a map entry or a sequence element.
+    Remove(String),
+    /// Renames the map entry at the path to the given key.
+    Rename(String, String),
+    /// Appends a value to the sequence at the path.
+    ArrayPush(String, Value),
+    /// Replaces `count` elements of the sequence at the path,
+    /// starting at `start`, with the given values.
+    ArraySplice(String, usize, usize, Vec<Value>),
+}
+
+impl Patch {
+    /// Applies the patch to `target`.
+    ///
+    /// If the patch fails, `target` is left unchanged.
+    pub fn apply(&self, target: &mut Value) -> Result<()> {
+        match *self {
+            Patch::Set(ref path, ref value) => {
+                if path.is_empty() {
+                    *target = value.clone();
+
+                    return Ok(());
+                }
+
+                let (parent, key) = split_last(path)?;
+                match *lookup_mut(target, parent)? {
+                    Value::Map(ref mut map) => {
+                        map.insert(Value::String(key.to_owned()), value.clone());
+                    }
+                    Value::Seq(ref mut seq) => {
+                        let i = index(seq, key).ok_or_else(|| Error::NotFound(path.clone()))?;
+                        seq[i] = value.clone();
+                    }
+                    _ => return Err(Error::WrongType(parent.to_owned())),
+                }
+            }
+            Patch::Remove(ref path) => {
+                let (parent, key) = split_last(path)?;
+                let removed = match *lookup_mut(target, parent)? {
+                    Value::Map(ref mut map) => map.remove(&Value::String(key.to_owned())),
+                    Value::Seq(ref mut seq) => index(seq, key).map(|i| seq.remove(i)),
+                    _ => return Err(Error::WrongType(parent.to_owned())),
+                };
+
+                removed.ok_or_else(|| Error::NotFound(path.clone()))?;
+            }
+            Patch::Rename(ref path, ref new_key) => {
+                let (parent, key) = split_last(path)?;
+                let map = match *lookup_mut(target, parent)? {
+                    Value::Map(ref mut map) => map,
+                    _ => return Err(Error::WrongType(parent.to_owned())),
+                };
+
+                let (old, new) = (Value::String(key.to_owned()), Value::String(new_key.clone()));
+                if !map.contains_key(&old) {
+                    return Err(Error::NotFound(path.clone()));
+                }
+                if old != new && map.contains_key(&new) {
+                    return Err(Error::KeyExists(format!("{}/{}", parent, new_key)));
+                }
+
+                // Rebuild the map so the entry keeps its position
+                *map = ::std::mem::replace(map, Map::new())
+                    .into_iter()
+                    .map(|(k, v)| if k == old { (new.clone(), v) } else { (k, v) })
+                    .collect();
+            }
+            Patch::ArrayPush(ref path, ref value) => {
+                lookup_seq(target, path)?.push(value.clone());
+            }
+            Patch::ArraySplice(ref path, start, count, ref values) => {
+                let seq = lookup_seq(target, path)?;
+                match start.checked_add(count) {
+                    Some(end) if end <= seq.len() => {
+                        seq.splice(start..end, values.iter().cloned());
+                    }
+                    _ => return Err(Error::OutOfRange(path.clone())),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the patch undoing this one, given the value before applying it.
+    ///
+    /// Returns `None` if this patch doesn't apply to `before`.
+    fn inverse(&self, before: &Value) -> Option<Patch> {
+        let inverse = match *self {
+            Patch::Set(ref path, _) => match lookup(before, path) {
+                Some(old) => Patch::Set(path.clone(), old.clone()),
+                None => Patch::Remove(path.clone()),
+            },
+            Patch::Remove(ref path) => {
+                let (parent, key) = split_last(path).ok()?;
+                let old = lookup(before, path)?.clone();
+
+                match *lookup(before, parent)? {
+                    Value::Seq(_) => Patch::ArraySplice(parent.to_owned(), key.parse().ok()?, 0, vec![old]),
+                    _ => Patch::Set(path.clone(), old),
+                }
+            }
+            Patch::Rename(ref path, ref new_key) => {
+                let (parent, key) = split_last(path).ok()?;
+
+                Patch::Rename(format!("{}/{}", parent, new_key), key.to_owned())
+            }
+            Patch::ArrayPush(ref path, _) => match *lookup(before, path)? {
+                Value::Seq(ref seq) => Patch::ArraySplice(path.clone(), seq.len(), 1, vec![]),
+                _ => return None,
+            },
+            Patch::ArraySplice(ref path, start, count, ref values) => match *lookup(before, path)? {
+                Value::Seq(ref seq) => {
+                    let removed = seq.get(start..start.checked_add(count)?)?.to_vec();
+
+                    Patch::ArraySplice(path.clone(), start, values.len(), removed)
+                }
+                _ => return None,
+            },
+        };
+
+        Some(inverse)
+    }
+}
+
+/// A list of patches, applied in order.
+///
+/// Serialized as RON, it makes for a readable migration script:
+///
+/// ```rust,ignore
+/// ([
+///     Rename("/server/addr", "address"),
+///     Set("/server/port", 8080),
+///     ArrayPush("/users", "admin"),
+/// ])
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PatchSet(pub Vec<Patch>);
+
+impl PatchSet {
+    /// Applies all patches to `target`.
+    ///
+    /// If any patch fails, `target` is left unchanged.
+    pub fn apply(&self, target: &mut Value) -> Result<()> {
+        let mut patched = target.clone();
+        for patch in &self.0 {
+            patch.apply(&mut patched)?;
+        }
+
+        *target = patched;
+
+        Ok(())
+    }
+
+    /// Returns the patches which roll back this set after it has been
+    /// applied to `original`.
+    ///
+    /// Removed map entries are appended to their map again,
+    /// so rolling back restores the entries but not necessarily their order.
+    /// Patches which fail to apply are left out.
+    pub fn inverse(&self, original: &Value) -> PatchSet {
+        let mut current = original.clone();
+        let mut inverse = Vec::new();
+
+        for patch in &self.0 {
+            if let Some(undo) = patch.inverse(&current) {
+                if patch.apply(&mut current).is_ok() {
+                    inverse.push(undo);
+                }
+            }
+        }
+
+        inverse.reverse();
+
+        PatchSet(inverse)
+    }
+}
+
+fn segments(path: &str) -> Result<Vec<&str>> {
+    if path.is_empty() {
+        Ok(vec![])
+    } else if let Some(rest) = path.strip_prefix('/') {
+        Ok(rest.split('/').collect())
+    } else {
+        Err(Error::InvalidPath(path.to_owned()))
+    }
+}
+
+/// Splits a path into the path of the parent and the last segment.
+fn split_last(path: &str) -> Result<(&str, &str)> {
+    match path.rfind('/') {
+        Some(i) if path.starts_with('/') => Ok((&path[..i], &path[i + 1..])),
+        _ => Err(Error::InvalidPath(path.to_owned())),
+    }
+}
+
+fn index(seq: &[Value], segment: &str) -> Option<usize> {
+    segment.parse().ok().filter(|&i| i < seq.len())
+}
+
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    segments(path).ok()?.into_iter().try_fold(value, |value, segment| match *value {
+        Value::Map(ref map) => map.get(&Value::String(segment.to_owned())),
+        Value::Seq(ref seq) => index(seq, segment).map(|i| &seq[i]),
+        _ => None,
+    })
+}
+
+fn lookup_mut<'a>(value: &'a mut Value, path: &str) -> Result<&'a mut Value> {
+    let mut value = value;
+    for segment in segments(path)? {
+        let child = match *value {
+            Value::Map(ref mut map) => map.get_mut(&Value::String(segment.to_owned())),
+            Value::Seq(ref mut seq) => index(seq, segment).map(move |i| &mut seq[i]),
+            _ => None,
+        };
+
+        value = child.ok_or_else(|| Error::NotFound(path.to_owned()))?;
+    }
+
+    Ok(value)
+}
+
+fn lookup_seq<'a>(value: &'a mut Value, path: &str) -> Result<&'a mut Vec<Value>> {
+    match *lookup_mut(value, path)? {
+        Value::Seq(ref mut seq) => Ok(seq),
+        _ => Err(Error::WrongType(path.to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(s: &str) -> Value {
+        Value::from_str(s).expect("Failed to parse")
+    }
+
+    fn config() -> Value {
+        eval("(server: (addr: \"localhost\", port: 80), users: [\"root\", \"guest\"])")
+    }
+
+    fn patched(patch: Patch) -> Result<Value> {
+        let mut value = config();
+        patch.apply(&mut value).map(|_| value)
+    }
+
+    #[test]
+    fn test_set() {
+        assert_eq!(patched(Patch::Set("/server/port".to_owned(), eval("8080"))),
+                   Ok(eval("(server: (addr: \"localhost\", port: 8080), users: [\"root\", \"guest\"])")));
+        assert_eq!(patched(Patch::Set("/server/tls".to_owned(), eval("true"))),
+                   Ok(eval("(server: (addr: \"localhost\", port: 80, tls: true), users: [\"root\", \"guest\"])")));
+        assert_eq!(patched(Patch::Set("/users/1".to_owned(), eval("\"admin\""))),
+                   Ok(eval("(server: (addr: \"localhost\", port: 80), users: [\"root\", \"admin\"])")));
+        assert_eq!(patched(Patch::Set("".to_owned(), eval("()"))), Ok(eval("()")));
+
+        assert_eq!(patched(Patch::Set("/users/2".to_owned(), eval("1"))),
+                   Err(Error::NotFound("/users/2".to_owned())));
+        assert_eq!(patched(Patch::Set("/client/port".to_owned(), eval("1"))),
+                   Err(Error::NotFound("/client".to_owned())));
+        assert_eq!(patched(Patch::Set("/server/port/x".to_owned(), eval("1"))),
+                   Err(Error::WrongType("/server/port".to_owned())));
+        assert_eq!(patched(Patch::Set("server".to_owned(), eval("1"))),
+                   Err(Error::InvalidPath("server".to_owned())));
+    }
+
+    #[test]
+    fn test_remove() {
+        assert_eq!(patched(Patch::Remove("/server/addr".to_owned())),
+                   Ok(eval("(server: (port: 80), users: [\"root\", \"guest\"])")));
+        assert_eq!(patched(Patch::Remove("/users/0".to_owned())),
+                   Ok(eval("(server: (addr: \"localhost\", port: 80), users: [\"guest\"])")));
+
+        assert_eq!(patched(Patch::Remove("/server/tls".to_owned())),
+                   Err(Error::NotFound("/server/tls".to_owned())));
+        assert_eq!(patched(Patch::Remove("".to_owned())), Err(Error::InvalidPath("".to_owned())));
+    }
+
+    #[test]
+    fn test_rename() {
+        assert_eq!(patched(Patch::Rename("/server/addr".to_owned(), "address".to_owned())),
+                   Ok(eval("(server: (address: \"localhost\", port: 80), users: [\"root\", \"guest\"])")));
+
+        assert_eq!(patched(Patch::Rename("/server/addr".to_owned(), "port".to_owned())),
+                   Err(Error::KeyExists("/server/port".to_owned())));
+        assert_eq!(patched(Patch::Rename("/users/0".to_owned(), "first".to_owned())),
+                   Err(Error::WrongType("/users".to_owned())));
+    }
+
+    #[test]
+    fn test_array_push() {
+        assert_eq!(patched(Patch::ArrayPush("/users".to_owned(), eval("\"admin\""))),
+                   Ok(eval("(server: (addr: \"localhost\", port: 80), users: [\"root\", \"guest\", \"admin\"])")));
+
+        assert_eq!(patched(Patch::ArrayPush("/server".to_owned(), eval("1"))),
+                   Err(Error::WrongType("/server".to_owned())));
+    }
+
+    #[test]
+    fn test_array_splice() {
+        let splice = |start, count| {
+            Patch::ArraySplice("/users".to_owned(), start, count, vec![eval("\"a\""), eval("\"b\"")])
+        };
+
+        assert_eq!(patched(splice(0, 1)),
+                   Ok(eval("(server: (addr: \"localhost\", port: 80), users: [\"a\", \"b\", \"guest\"])")));
+        assert_eq!(patched(splice(2, 0)),
+                   Ok(eval("(server: (addr: \"localhost\", port: 80), users: [\"root\", \"guest\", \"a\", \"b\"])")));
+
+        assert_eq!(patched(splice(1, 2)), Err(Error::OutOfRange("/users".to_owned())));
+    }
+
+    #[test]
+    fn test_patch_set() {
+        let patches = PatchSet(vec![
+            Patch::Rename("/server/addr".to_owned(), "address".to_owned()),
+            Patch::Set("/server/port".to_owned(), eval("8080")),
+            Patch::Remove("/users/0".to_owned()),
+            Patch::ArrayPush("/users".to_owned(), eval("\"admin\"")),
+            Patch::ArraySplice("/users".to_owned(), 0, 1, vec![]),
+            Patch::Set("/server/tls".to_owned(), eval("true")),
+        ]);
+
+        let original = config();
+        let mut value = original.clone();
+        patches.apply(&mut value).unwrap();
+        assert_eq!(value, eval("(server: (address: \"localhost\", port: 8080, tls: true), users: [\"admin\"])"));
+
+        let inverse = patches.inverse(&original);
+        inverse.apply(&mut value).unwrap();
+        assert_eq!(value, original);
+
+        let failing = PatchSet(vec![Patch::Remove("/server".to_owned()), Patch::Remove("/server".to_owned())]);
+        assert_eq!(failing.apply(&mut value), Err(Error::NotFound("/server".to_owned())));
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn test_ron_script() {
+        let script = "([
+            Rename(\"/server/addr\", \"address\"),
+            Set(\"/server/port\", 8080),
+            ArraySplice(\"/users\", 0, 1, []),
+        ])";
+
+        let patches: PatchSet = ::de::from_str(script).unwrap();
+        assert_eq!(patches, PatchSet(vec![
+            Patch::Rename("/server/addr".to_owned(), "address".to_owned()),
+            Patch::Set("/server/port".to_owned(), eval("8080")),
+            Patch::ArraySplice("/users".to_owned(), 0, 1, vec![]),
+        ]));
+
+        let s = ::ser::to_string(&patches).unwrap();
+        assert_eq!(::de::from_str::<PatchSet>(&s), Ok(patches));
+    }
+}
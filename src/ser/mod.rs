@@ -1,11 +1,21 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "std")]
 use std::error::Error as StdError;
 use std::result::Result as StdResult;
-use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fmt::{self, Display, Formatter, Result as FmtResult};
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
 use serde::ser::{self, Serialize};
 
+use extensions::Extensions;
+
+#[cfg(feature = "std")]
+pub use self::value::{to_ron_value, ValueSerializer};
+
 #[deprecated(since="0.1.4", note="please use `to_string_pretty` with `PrettyConfig::default()` instead")]
 pub mod pretty;
-mod value;
+#[cfg(feature = "std")]
+pub mod value;
 
 /// Serializes `value` and returns it as string.
 ///
@@ -14,31 +24,134 @@ mod value;
 pub fn to_string<T>(value: &T) -> Result<String>
     where T: Serialize
 {
-    let mut s = Serializer {
-        output: String::new(),
-        pretty: (PrettyConfig::basic(false), Pretty { indent: 0 }),
-    };
-    value.serialize(&mut s)?;
-    Ok(s.output)
+    to_string_pretty(value, PrettyConfig::basic(false))
 }
 
 /// Serializes `value` in the recommended RON layout in a pretty way.
 pub fn to_string_pretty<T>(value: &T, config: PrettyConfig) -> Result<String>
     where T: Serialize
 {
-    let mut s = Serializer {
-        output: String::new(),
-        pretty: (config, Pretty { indent: 0 }),
-    };
+    let mut s = Serializer::new(String::new(), config)?;
     value.serialize(&mut s)?;
     Ok(s.output)
 }
 
+/// Serializes `value` like `to_string`, but with the entries of maps sorted.
+///
+/// The entries are sorted lexicographically by the serialized text of their
+/// keys, so hash maps always give the same output, e.g. for comparing
+/// it in tests. Note that `10` is sorted before `9`.
+pub fn to_string_sorted<T>(value: &T) -> Result<String>
+    where T: Serialize
+{
+    to_string_pretty(value, PrettyConfig::basic(false).with_sort_map_keys(true))
+}
+
+/// Serializes `value` like `to_string_pretty` with `config`,
+/// but with the entries of maps sorted like by `to_string_sorted`.
+pub fn to_string_pretty_sorted<T>(value: &T, config: PrettyConfig) -> Result<String>
+    where T: Serialize
+{
+    to_string_pretty(value, config.with_sort_map_keys(true))
+}
+
+/// Writes `value` to a formatter, for implementing `Display` in terms of RON.
+///
+/// Nothing is buffered, the output goes straight to `fmt`.
+/// Serialization errors are reported as `fmt::Error`.
+pub fn write_to_fmt<T>(value: &T, fmt: &mut Formatter, config: &PrettyConfig) -> FmtResult
+    where T: Serialize
+{
+    let mut s = Serializer::new(fmt, config.clone()).map_err(|_| fmt::Error)?;
+    value.serialize(&mut s).map_err(|_| fmt::Error)
+}
+
+/// Serializes `value` like `to_string` into the fixed-size buffer `buf`,
+/// returning the number of bytes written.
+///
+/// The output is not allocated, so together with the crate being `no_std`
+/// without the `std` feature, this is meant for embedded use. Fails if the
+/// output does not fit into the buffer.
+pub fn to_string_fixed<T, const N: usize>(value: &T, buf: &mut [u8; N]) -> Result<usize>
+    where T: Serialize
+{
+    let output = FixedBuf { buf: &mut buf[..], len: 0, overflowed: false };
+    let mut s = Serializer::new(output, PrettyConfig::basic(false))?;
+    value.serialize(&mut s).map_err(|e| if s.output.overflowed {
+        Error::Message("the output does not fit into the buffer".to_owned())
+    } else {
+        e
+    })?;
+
+    Ok(s.output.len)
+}
+
+/// A `fmt::Write` filling a byte slice.
+struct FixedBuf<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    /// Set when a write did not fit, to tell this error from others
+    overflowed: bool,
+}
+
+impl<'a> fmt::Write for FixedBuf<'a> {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            self.overflowed = true;
+            return Err(fmt::Error);
+        }
+
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Serializes `value` like `to_string`, but returns the UTF-8 bytes.
+///
+/// The output is written straight into a byte buffer, which is handed out
+/// without a copy.
+pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>>
+    where T: Serialize
+{
+    let mut s = Serializer::new(ByteBuf(Vec::new()), PrettyConfig::basic(false))?;
+    value.serialize(&mut s)?;
+    Ok(s.output.0)
+}
+
+/// A `fmt::Write` appending to a byte vector.
+struct ByteBuf(Vec<u8>);
+
+impl fmt::Write for ByteBuf {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Serializes `value` in a pretty way, emitting the names of structs.
+///
+/// This is a shorthand for `to_string_pretty` with `PrettyConfig::default()`.
+pub fn to_string_with_struct_names<T>(value: &T) -> Result<String>
+    where T: Serialize
+{
+    to_string_pretty(value, PrettyConfig::default())
+}
+
+/// Serializes `value` in a pretty way, leaving out the names of structs.
+pub fn to_string_without_struct_names<T>(value: &T) -> Result<String>
+    where T: Serialize
+{
+    to_string_pretty(value, PrettyConfig::default_with(|x| x.struct_names = false))
+}
+
 /// Serialization result.
 pub type Result<T> = StdResult<T, Error>;
 
 /// Serialization error.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
     /// A custom error emitted by a serialized value.
     Message(String),
@@ -47,17 +160,24 @@ pub enum Error {
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match *self {
-            Error::Message(ref e) => write!(f, "Custom message: {}", e),
+            Error::Message(ref e) => write!(f, "{}", e),
         }
     }
 }
 
+impl From<fmt::Error> for Error {
+    fn from(e: fmt::Error) -> Self {
+        Error::Message(e.to_string())
+    }
+}
+
 impl ser::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
         Error::Message(msg.to_string())
     }
 }
 
+#[cfg(feature = "std")]
 impl StdError for Error {
     fn description(&self) -> &str {
         match *self {
@@ -66,13 +186,42 @@ impl StdError for Error {
     }
 }
 
+#[cfg(not(feature = "std"))]
+impl ser::StdError for Error {}
+
 /// Pretty serializer state
+#[derive(Default)]
 struct Pretty {
     indent: usize,
+    /// Whether the members of each currently open tuple are separated
+    separate_tuple_members: Vec<bool>,
+}
+
+/// How comments are written into the output
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum CommentStyle {
+    /// `// comment`, one per line of the comment
+    #[default]
+    Line,
+    /// `/* comment */`
+    Block,
+}
+
+/// Which values are annotated with their type
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum AnnotationStyle {
+    /// No annotations
+    #[default]
+    None,
+    /// Numbers get the Rust type as a literal suffix, like `4i64` or `0.5f32`
+    Suffix,
+    /// Like `Suffix`, and booleans are annotated like `true:bool`
+    CoercedSuffix,
 }
 
 /// Pretty serializer configuration
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[allow(clippy::manual_non_exhaustive)]
 pub struct PrettyConfig {
     /// New line string
     pub new_line: String,
@@ -84,6 +233,34 @@ pub struct PrettyConfig {
     pub struct_names: bool,
     /// Add spaces after commas between elements in tuples and maps
     pub add_space: bool,
+    /// Keep tuples with fewer members than this on one line,
+    /// even if `separate_tuple_members` is set
+    #[serde(default)]
+    pub inline_tuples_shorter_than: Option<usize>,
+    /// Extensions which change the representation of certain types
+    #[serde(default)]
+    pub extensions: Extensions,
+    /// The style of the comments in `field_comments`
+    #[serde(default)]
+    pub comment_style: CommentStyle,
+    /// Comments to write before struct fields, by field name
+    #[serde(default)]
+    pub field_comments: Option<BTreeMap<String, String>>,
+    /// Write structs without fields as just their name, like unit structs,
+    /// instead of `Name()`. Has no effect unless `struct_names` is set.
+    #[serde(default)]
+    pub omit_struct_parens_when_no_fields: bool,
+    /// Write the entries of maps sorted by the text of their keys,
+    /// for deterministic output of hash maps
+    #[serde(default)]
+    pub sort_map_keys: bool,
+    /// Annotate numbers (and booleans) with their types
+    #[serde(default)]
+    pub annotation_style: AnnotationStyle,
+    /// Write a `// index <n>` comment before each enum variant,
+    /// giving its index in the enum
+    #[serde(default)]
+    pub emit_variant_index_comment: bool,
     #[serde(skip)]
     _dummy: (),
 }
@@ -99,6 +276,14 @@ impl Default for PrettyConfig {
             separate_tuple_members: false,
             struct_names: true,
             add_space: true,
+            inline_tuples_shorter_than: None,
+            extensions: Extensions::empty(),
+            comment_style: CommentStyle::default(),
+            field_comments: None,
+            omit_struct_parens_when_no_fields: false,
+            sort_map_keys: false,
+            emit_variant_index_comment: false,
+            annotation_style: AnnotationStyle::None,
             _dummy: ()
         }
     }
@@ -113,6 +298,21 @@ impl PrettyConfig {
         cfg
     }
 
+    /// Sets the extensions to serialize with.
+    ///
+    /// The extensions are announced with `#![enable(...)]` attributes
+    /// at the start of the output, so the deserializer picks them up.
+    pub fn with_extensions(mut self, extensions: Extensions) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Sets whether to sort the entries of maps by their keys.
+    pub fn with_sort_map_keys(mut self, sort_map_keys: bool) -> Self {
+        self.sort_map_keys = sort_map_keys;
+        self
+    }
+
     pub fn basic(struct_names: bool) -> PrettyConfig {
         PrettyConfig::default_with(|x|{
             x.new_line = String::from("");
@@ -124,23 +324,345 @@ impl PrettyConfig {
     }
 }
 
+/// The name `RawStr` passes to `serialize_newtype_struct`,
+/// telling the RON serializer to write a raw string literal.
+const RAW_STR_NAME: &str = "$ron::RawStr";
+
+/// A string which is serialized as a raw string literal like `r#"C:\path"#`
+/// by RON, and as an ordinary string by other formats.
+///
+/// Useful in `Serialize` implementations for strings with many
+/// backslashes or quotes, like regular expressions or Windows paths.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RawStr<'a>(pub &'a str);
+
+impl<'a> Serialize for RawStr<'a> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+        where S: ser::Serializer
+    {
+        serializer.serialize_newtype_struct(RAW_STR_NAME, self.0)
+    }
+}
+
+/// The name `Commented` passes to `serialize_newtype_struct`, telling
+/// the RON serializer that the name of the newtype struct inside is a comment.
+const COMMENTED_NAME: &str = "$ron::Commented";
+
+/// A value which RON writes with the second field as a line comment before it,
+/// and which other formats serialize like the value itself.
+///
+/// Inside a struct field, the comment goes before the field's key.
+/// Like `Serializer::serialize_comment`, it is dropped from output
+/// without new lines.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Commented<T>(pub T, pub &'static str);
+
+impl<T: Serialize> Serialize for Commented<T> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+        where S: ser::Serializer
+    {
+        serializer.serialize_newtype_struct(COMMENTED_NAME, &CommentedValue(&self.0, self.1))
+    }
+}
+
+/// The inside of a `Commented`, a newtype struct named after the comment.
+struct CommentedValue<'a, T: 'a>(&'a T, &'static str);
+
+impl<'a, T: Serialize> Serialize for CommentedValue<'a, T> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+        where S: ser::Serializer
+    {
+        serializer.serialize_newtype_struct(self.1, self.0)
+    }
+}
+
 /// The RON serializer.
 ///
 /// You can just use `to_string` for deserializing a value.
 /// If you want it pretty-printed, take a look at the `pretty` module.
-pub struct Serializer {
-    output: String,
+pub struct Serializer<W = String> {
+    output: W,
     pretty: (PrettyConfig, Pretty),
+    /// Set while serializing the value of a newtype variant whose
+    /// parentheses may be merged with the ones of the variant
+    newtype_variant: bool,
+    /// Whether each currently open struct or tuple had its parentheses elided
+    unwrapped: Vec<bool>,
+    /// Set after the comma of an inline tuple member; the space following
+    /// it is only written if another member comes
+    pending_space: bool,
+    /// Set while serializing the contents of a `RawStr`
+    raw_str: bool,
+    /// Set while serializing a map key, whose booleans are not annotated
+    map_key: bool,
+    /// Set while serializing the contents of a `Commented`,
+    /// until the newtype struct holding its comment
+    commented: bool,
+    /// Set between the start and the end of a struct without fields
+    /// which was written without parentheses
+    omitted_parens: bool,
+    /// The entries of each currently open map whose keys are sorted,
+    /// rendered to strings and waiting to be written at its end
+    sorted_maps: Vec<SortedMap>,
+    /// The key of the struct field being serialized, until its value
+    /// starts, so comments on the value can go before the key
+    pending_field: Option<&'static str>,
+}
+
+/// The entries of a map collected for sorting.
+#[derive(Default)]
+struct SortedMap {
+    entries: Vec<(String, String)>,
+    /// The rendered key waiting for its value
+    key: String,
 }
 
-impl Serializer {
+impl<W: fmt::Write> Serializer<W> {
+    /// Creates a serializer writing to `output`, for driving it by hand
+    /// with methods like `serialize_str_raw` between ordinary values.
+    ///
+    /// The `#![enable(...)]` attributes of the extensions in `config`
+    /// are written right away.
+    pub fn new(output: W, config: PrettyConfig) -> Result<Self> {
+        let mut serializer = Serializer {
+            output,
+            pretty: (config, Pretty::default()),
+            newtype_variant: false,
+            unwrapped: Vec::new(),
+            pending_space: false,
+            raw_str: false,
+            map_key: false,
+            commented: false,
+            omitted_parens: false,
+            sorted_maps: Vec::new(),
+            pending_field: None,
+        };
+
+        for ident in serializer.extensions().idents() {
+            serializer.write("#![enable(")?;
+            serializer.write(ident)?;
+            serializer.write(")]")?;
+            serializer.write(&serializer.new_line())?;
+        }
+
+        Ok(serializer)
+    }
+
+    /// Returns the output written so far.
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+
+    /// Writes `s` as a raw string literal like `r#"a "quoted" word"#`,
+    /// using as few `#`s as possible.
+    pub fn serialize_str_raw(&mut self, s: &str) -> Result<()> {
+        // The literal ends at the first quote followed by enough `#`s,
+        // so use one more than the longest run following any quote
+        let hashes = s
+            .match_indices('"')
+            .map(|(i, _)| s[i + 1..].bytes().take_while(|&b| b == b'#').count() + 1)
+            .max()
+            .unwrap_or(0);
+
+        self.write("r")?;
+        for _ in 0..hashes {
+            self.write("#")?;
+        }
+        self.write("\"")?;
+        self.write(s)?;
+        self.write("\"")?;
+        for _ in 0..hashes {
+            self.write("#")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `comment` as a line comment, followed by a new line and the
+    /// current indentation, so the next value starts on a line of its own.
+    ///
+    /// Does nothing if the config has no new line, as a line comment
+    /// would swallow the rest of the compact output.
+    ///
+    /// Inside a struct field, the comment goes before the field's key.
+    pub fn serialize_comment(&mut self, comment: &str) -> Result<()> {
+        if self.pretty.0.new_line.is_empty() {
+            return Ok(());
+        }
+
+        let field = self.pending_field.take();
+        for line in comment.lines() {
+            self.write("// ")?;
+            self.write(line)?;
+            self.write(&self.new_line())?;
+            self.indent()?;
+        }
+        self.pending_field = field;
+
+        Ok(())
+    }
+
+    /// Serializes `value` into a string, as it would be written
+    /// at the current indentation.
+    fn render<T>(&self, value: &T) -> Result<String>
+        where T: ?Sized + Serialize
+    {
+        let mut serializer = Serializer {
+            output: String::new(),
+            pretty: (self.pretty.0.clone(), Pretty { indent: self.pretty.1.indent, ..Pretty::default() }),
+            newtype_variant: false,
+            unwrapped: Vec::new(),
+            pending_space: false,
+            raw_str: false,
+            map_key: self.map_key,
+            commented: false,
+            omitted_parens: false,
+            sorted_maps: Vec::new(),
+            pending_field: None,
+        };
+        value.serialize(&mut serializer)?;
+
+        Ok(serializer.output)
+    }
+
+    /// Writes the number `n`, followed by the type suffix `ty`
+    /// if the config asks for annotations.
+    fn write_number<N: Display>(&mut self, n: N, ty: &str) -> Result<()> {
+        self.write(&n.to_string())?;
+
+        if self.pretty.0.annotation_style != AnnotationStyle::None {
+            self.write(ty)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a float, with a type suffix unless it is infinite or NaN,
+    /// as `inff64` would be an identifier.
+    fn write_float(&mut self, v: f64, ty: &str) -> Result<()> {
+        if v.is_finite() {
+            self.write_number(v, ty)
+        } else {
+            self.write(&v.to_string())
+        }
+    }
+
+    /// Writes the comment giving the index of an enum variant,
+    /// if enabled in the config.
+    fn variant_index_comment(&mut self, index: u32) -> Result<()> {
+        if self.pretty.0.emit_variant_index_comment {
+            self.serialize_comment(&format!("index {}", index))?;
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, s: &str) -> Result<()> {
+        if let Some(key) = self.pending_field.take() {
+            let space = self.space();
+            self.output.write_str(key)?;
+            self.output.write_str(":")?;
+            self.output.write_str(&space)?;
+        }
+
+        self.output.write_str(s)?;
+        Ok(())
+    }
+
+    /// Returns whether the parentheses of the current value should be
+    /// elided, resetting the flag for any nested values.
+    fn take_newtype_variant(&mut self) -> bool {
+        ::std::mem::replace(&mut self.newtype_variant, false)
+    }
+
+    /// Opens a struct or tuple, omitting the opening parenthesis (and the name)
+    /// if it is the value of an unwrapped newtype variant.
+    fn start_compound(&mut self, name: Option<&str>) -> Result<()> {
+        let unwrapped = self.take_newtype_variant();
+        self.unwrapped.push(unwrapped);
+
+        if !unwrapped {
+            if let Some(name) = name {
+                self.write(name)?;
+            }
+            self.write("(")?;
+        }
+
+        Ok(())
+    }
+
+    fn end_compound(&mut self) -> Result<()> {
+        if !self.unwrapped.pop().unwrap_or(false) {
+            self.write(")")?;
+        }
+
+        Ok(())
+    }
+
     fn separate_tuple_members(&self) -> bool {
-        self.pretty.0.separate_tuple_members
+        self.pretty.1.separate_tuple_members.last().cloned().unwrap_or(false)
+    }
+
+    fn start_tuple(&mut self, len: usize) -> Result<()> {
+        let separate = {
+            let config = &self.pretty.0;
+            config.separate_tuple_members &&
+                config.inline_tuples_shorter_than.map(|min| len >= min).unwrap_or(true)
+        };
+        self.pretty.1.separate_tuple_members.push(separate);
+
+        if separate {
+            self.start_indent()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the comment for the struct field `key`, if there is one,
+    /// followed by a new line and the indentation of the field.
+    fn field_comment(&mut self, key: &str) -> Result<()> {
+        let comment = match self.pretty.0.field_comments.as_ref().and_then(|c| c.get(key)) {
+            Some(comment) => comment.clone(),
+            None => return Ok(()),
+        };
+
+        match self.pretty.0.comment_style {
+            CommentStyle::Line => {
+                // A line comment has to end with a line break,
+                // even in the compact output
+                let new_line = match self.new_line() {
+                    ref nl if nl.is_empty() => "\n".to_string(),
+                    nl => nl,
+                };
+
+                for (i, line) in comment.lines().enumerate() {
+                    if i > 0 {
+                        self.indent()?;
+                    }
+                    self.write("// ")?;
+                    self.write(line)?;
+                    self.write(&new_line)?;
+                }
+            }
+            CommentStyle::Block => {
+                // Block comments nest, so both delimiters are broken up
+                self.write("/* ")?;
+                self.write(&comment.replace("*/", "* /").replace("/*", "/ *"))?;
+                self.write(" */")?;
+                self.write(&self.new_line())?;
+            }
+        }
+
+        self.indent()
     }
 
     fn struct_names(&self) -> bool {
         self.pretty.0.struct_names
     }
+
+    fn extensions(&self) -> Extensions {
+        self.pretty.0.extensions
+    }
     
     fn new_line(&self) -> String {
         self.pretty.0.new_line.clone()
@@ -151,25 +673,28 @@ impl Serializer {
     }
 
 
-    fn start_indent(&mut self) {
+    fn start_indent(&mut self) -> Result<()> {
         let (ref config, ref mut pretty) = self.pretty;
         pretty.indent += 1;
-        self.output += &config.new_line;
+        self.output.write_str(&config.new_line)?;
+        Ok(())
     }
 
-    fn indent(&mut self) {
+    fn indent(&mut self) -> Result<()> {
         let (ref config, ref pretty) = self.pretty;
-        self.output.extend((0..pretty.indent).map(|_| config.indentor.as_str()));
+        for _ in 0..pretty.indent {
+            self.output.write_str(&config.indentor)?;
+        }
+        Ok(())
     }
 
-    fn end_indent(&mut self) {
-        let (ref config, ref mut pretty) = self.pretty;
-        pretty.indent -= 1;
-        self.output.extend((0..pretty.indent).map(|_| config.indentor.as_str()));
+    fn end_indent(&mut self) -> Result<()> {
+        self.pretty.1.indent -= 1;
+        self.indent()
     }
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl<W: fmt::Write> ser::Serializer for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -182,73 +707,84 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     type SerializeStructVariant = Self;
 
     fn serialize_bool(self, v: bool) -> Result<()> {
-        self.output += if v { "true" } else { "false" };
+        self.write(if v { "true" } else { "false" })?;
+
+        if self.pretty.0.annotation_style == AnnotationStyle::CoercedSuffix && !self.map_key {
+            self.write(":bool")?;
+        }
+
         Ok(())
     }
 
     fn serialize_i8(self, v: i8) -> Result<()> {
-        self.serialize_i64(v as i64)
+        self.write_number(v, "i8")
     }
 
     fn serialize_i16(self, v: i16) -> Result<()> {
-        self.serialize_i64(v as i64)
+        self.write_number(v, "i16")
     }
 
     fn serialize_i32(self, v: i32) -> Result<()> {
-        self.serialize_i64(v as i64)
+        self.write_number(v, "i32")
     }
 
     fn serialize_i64(self, v: i64) -> Result<()> {
-        // TODO optimize
-        self.output += &v.to_string();
-        Ok(())
+        self.write_number(v, "i64")
     }
 
     fn serialize_u8(self, v: u8) -> Result<()> {
-        self.serialize_u64(v as u64)
+        self.write_number(v, "u8")
     }
 
     fn serialize_u16(self, v: u16) -> Result<()> {
-        self.serialize_u64(v as u64)
+        self.write_number(v, "u16")
     }
 
     fn serialize_u32(self, v: u32) -> Result<()> {
-        self.serialize_u64(v as u64)
+        self.write_number(v, "u32")
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
-        self.output += &v.to_string();
-        Ok(())
+        self.write_number(v, "u64")
     }
 
     fn serialize_f32(self, v: f32) -> Result<()> {
-        self.serialize_f64(v as f64)
+        self.write_float(v as f64, "f32")
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
-        self.output += &v.to_string();
-        Ok(())
+        self.write_float(v, "f64")
     }
 
     fn serialize_char(self, v: char) -> Result<()> {
-        self.output += "'";
-        if v == '\\' || v == '\'' {
-            self.output.push('\\');
+        self.write("'")?;
+        match v {
+            '\\' => self.write("\\\\")?,
+            '\'' => self.write("\\'")?,
+            '\n' => self.write("\\n")?,
+            '\r' => self.write("\\r")?,
+            '\t' => self.write("\\t")?,
+            '\0' => self.write("\\0")?,
+            c if c.is_control() => write!(self.output, "\\u{{{:x}}}", c as u32)?,
+            c => self.output.write_char(c)?,
         }
-        self.output.push(v);
-        self.output += "'";
+        self.write("'")?;
         Ok(())
     }
 
     fn serialize_str(self, v: &str) -> Result<()> {
-        self.output += "\"";
+        if ::std::mem::replace(&mut self.raw_str, false) {
+            return self.serialize_str_raw(v);
+        }
+
+        self.write("\"")?;
         for char in v.chars() {
             if char == '\\' || char == '"' {
-                self.output.push('\\');
+                self.write("\\")?;
             }
-            self.output.push(char);
+            self.output.write_char(char)?;
         }
-        self.output += "\"";
+        self.write("\"")?;
         Ok(())
     }
 
@@ -262,7 +798,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_none(self) -> Result<()> {
-        self.output += "None";
+        self.write("None")?;
 
         Ok(())
     }
@@ -270,22 +806,29 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_some<T>(self, value: &T) -> Result<()>
         where T: ?Sized + Serialize
     {
-        self.output += "Some(";
+        self.newtype_variant = false;
+
+        let implicit_some = self.extensions().contains(Extensions::IMPLICIT_SOME);
+        if !implicit_some {
+            self.write("Some(")?;
+        }
         value.serialize(&mut *self)?;
-        self.output += ")";
+        if !implicit_some {
+            self.write(")")?;
+        }
 
         Ok(())
     }
 
     fn serialize_unit(self) -> Result<()> {
-        self.output += "()";
+        self.write("()")?;
 
         Ok(())
     }
 
     fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
         if self.struct_names() {
-            self.output += name;
+            self.write(name)?;
 
             Ok(())
         } else {
@@ -296,10 +839,11 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_unit_variant(
         self,
         _: &'static str,
-        _: u32,
+        index: u32,
         variant: &'static str
     ) -> Result<()> {
-        self.output += variant;
+        self.variant_index_comment(index)?;
+        self.write(variant)?;
 
         Ok(())
     }
@@ -307,48 +851,73 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
         where T: ?Sized + Serialize
     {
+        if name == RAW_STR_NAME {
+            self.raw_str = true;
+            let result = value.serialize(&mut *self);
+            self.raw_str = false;
+
+            return result;
+        }
+
+        if name == COMMENTED_NAME {
+            self.commented = true;
+            return value.serialize(&mut *self);
+        }
+
+        if self.commented {
+            self.commented = false;
+            self.serialize_comment(name)?;
+            return value.serialize(&mut *self);
+        }
+
+        if self.take_newtype_variant() || self.extensions().contains(Extensions::UNWRAP_NEWTYPES) {
+            return value.serialize(&mut *self);
+        }
+
         if self.struct_names() {
-            self.output += name;
+            self.write(name)?;
         }
 
-        self.output += "(";
+        self.write("(")?;
         value.serialize(&mut *self)?;
-        self.output += ")";
+        self.write(")")?;
         Ok(())
     }
 
     fn serialize_newtype_variant<T>(
         self,
         _: &'static str,
-        _: u32,
+        index: u32,
         variant: &'static str,
         value: &T
     ) -> Result<()>
         where T: ?Sized + Serialize
     {
-        self.output += variant;
-        self.output += "(";
+        self.variant_index_comment(index)?;
+        self.write(variant)?;
+        self.write("(")?;
 
+        self.newtype_variant = self.extensions().contains(Extensions::UNWRAP_VARIANT_NEWTYPES);
         value.serialize(&mut *self)?;
+        self.newtype_variant = false;
 
-        self.output += ")";
+        self.write(")")?;
         Ok(())
     }
 
     fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq> {
-        self.output += "[";
+        self.newtype_variant = false;
+        self.write("[")?;
 
-        self.start_indent();
+        self.start_indent()?;
 
         Ok(self)
     }
 
-    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple> {
-        self.output += "(";
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.start_compound(None)?;
 
-        if self.separate_tuple_members() {
-            self.start_indent();
-        }
+        self.start_tuple(len)?;
 
         Ok(self)
     }
@@ -358,34 +927,39 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         name: &'static str,
         len: usize
     ) -> Result<Self::SerializeTupleStruct> {
-        if self.struct_names() {
-            self.output += name;
-        }
+        let name = if self.struct_names() { Some(name) } else { None };
+        self.start_compound(name)?;
 
-        self.serialize_tuple(len)
+        self.start_tuple(len)?;
+
+        Ok(self)
     }
 
     fn serialize_tuple_variant(
         self,
         _: &'static str,
-        _: u32,
+        index: u32,
         variant: &'static str,
-        _: usize
+        len: usize
     ) -> Result<Self::SerializeTupleVariant> {
-        self.output += variant;
-        self.output += "(";
+        self.newtype_variant = false;
+        self.variant_index_comment(index)?;
+        self.start_compound(Some(variant))?;
 
-        if self.separate_tuple_members() {
-            self.start_indent();
-        }
+        self.start_tuple(len)?;
 
         Ok(self)
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        self.output += "{";
+        self.newtype_variant = false;
+        self.write("{")?;
+
+        if self.pretty.0.sort_map_keys {
+            self.sorted_maps.push(SortedMap::default());
+        }
 
-        self.start_indent();
+        self.start_indent()?;
 
         Ok(self)
     }
@@ -393,14 +967,21 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_struct(
         self,
         name: &'static str,
-        _: usize
+        len: usize
     ) -> Result<Self::SerializeStruct> {
-        if self.struct_names() {
-            self.output += name;
+        let omit_parens = len == 0 && self.struct_names() && !self.newtype_variant &&
+            self.pretty.0.omit_struct_parens_when_no_fields;
+        if omit_parens {
+            self.omitted_parens = true;
+            self.write(name)?;
+
+            return Ok(self);
         }
-        self.output += "(";
 
-        self.start_indent();
+        let name = if self.struct_names() { Some(name) } else { None };
+        self.start_compound(name)?;
+
+        self.start_indent()?;
 
         Ok(self)
     }
@@ -408,43 +989,95 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_struct_variant(
         self,
         _: &'static str,
-        _: u32,
+        index: u32,
         variant: &'static str,
         _: usize
     ) -> Result<Self::SerializeStructVariant> {
-        self.output += variant;
-        self.output += "(";
+        self.newtype_variant = false;
+        self.variant_index_comment(index)?;
+        self.start_compound(Some(variant))?;
 
-        self.start_indent();
+        self.start_indent()?;
 
         Ok(self)
     }
+
+    /// With `sort_map_keys`, only the keys are rendered up front to sort
+    /// the entries, and the values are written directly afterwards,
+    /// instead of buffering whole entries in `SerializeMap`.
+    fn collect_map<K, V, I>(self, iter: I) -> Result<()>
+        where K: Serialize, V: Serialize, I: IntoIterator<Item = (K, V)>
+    {
+        use serde::ser::SerializeMap;
+
+        if !self.pretty.0.sort_map_keys {
+            let mut map = self.serialize_map(None)?;
+            for (key, value) in iter {
+                map.serialize_entry(&key, &value)?;
+            }
+
+            return map.end();
+        }
+
+        self.newtype_variant = false;
+        self.write("{")?;
+        self.start_indent()?;
+
+        // Keys are rendered at the indentation they are written at
+        self.map_key = true;
+        let entries = iter
+            .into_iter()
+            .map(|(key, value)| Ok((self.render(&key)?, value)))
+            .collect::<Result<Vec<_>>>();
+        self.map_key = false;
+
+        let mut entries = entries?;
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (key, value) in entries {
+            self.indent()?;
+            self.write(&key)?;
+            self.write(":")?;
+            self.write(&self.space())?;
+            value.serialize(&mut *self)?;
+            self.write(",")?;
+            self.write(&self.new_line())?;
+        }
+
+        self.end_indent()?;
+        self.write("}")
+    }
+
+    /// RON is a human-readable format.
+    fn is_human_readable(&self) -> bool {
+        true
+    }
 }
 
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl<W: fmt::Write> ser::SerializeSeq for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
     fn serialize_element<T>(&mut self, value: &T) -> Result<()>
         where T: ?Sized + Serialize
     {
-        self.indent();
+        self.indent()?;
         value.serialize(&mut **self)?;
-        self.output += ",";
-        self.output += &self.new_line();
+        self.write(",")?;
+        self.write(&self.new_line())?;
 
         Ok(())
     }
 
     fn end(self) -> Result<()> {
-        self.end_indent();
+        self.end_indent()?;
 
-        self.output += "]";
+        self.write("]")?;
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl<W: fmt::Write> ser::SerializeTuple for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -452,37 +1085,37 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
         where T: ?Sized + Serialize
     {
         if self.separate_tuple_members() {
-            self.indent();
+            self.indent()?;
+        } else if self.pending_space {
+            self.pending_space = false;
+            self.write(&self.space())?;
         }
         value.serialize(&mut **self)?;
-        self.output += ",";
+        self.write(",")?;
         
         if self.separate_tuple_members() { 
-            self.output += &self.new_line(); 
+            self.write(&self.new_line())?; 
         } else { 
-            self.output += &self.space();
+            self.pending_space = true;
         };
         Ok(())
     }
 
     fn end(self) -> Result<()> {
         if self.separate_tuple_members() {
-            self.end_indent();
-        } else {
-            let len = self.space().len();
-            for _ in 0..len {
-                self.output.pop();
-            }
+            self.end_indent()?;
         }
+        self.pending_space = false;
+        self.pretty.1.separate_tuple_members.pop();
 
-        self.output += ")";
+        self.end_compound()?;
 
         Ok(())
     }
 }
 
 // Same thing but for tuple structs.
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl<W: fmt::Write> ser::SerializeTupleStruct for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -497,7 +1130,7 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl<W: fmt::Write> ser::SerializeTupleVariant for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -512,64 +1145,122 @@ impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl<W: fmt::Write> ser::SerializeMap for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
     fn serialize_key<T>(&mut self, key: &T) -> Result<()>
         where T: ?Sized + Serialize
     {
-        self.indent();
+        // Booleans in keys are not annotated, as `true:bool:1` is hard to read
+        self.map_key = true;
+        let result = if self.sorted_maps.is_empty() {
+            self.indent().and_then(|_| key.serialize(&mut **self))
+        } else {
+            self.render(key).map(|key| {
+                if let Some(map) = self.sorted_maps.last_mut() {
+                    map.key = key;
+                }
+            })
+        };
+        self.map_key = false;
 
-        key.serialize(&mut **self)
+        result
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
         where T: ?Sized + Serialize
     {
-        self.output += ":";
-        self.output += &self.space();
+        if !self.sorted_maps.is_empty() {
+            let value = self.render(value)?;
+            if let Some(map) = self.sorted_maps.last_mut() {
+                let key = ::std::mem::take(&mut map.key);
+                map.entries.push((key, value));
+            }
+
+            return Ok(());
+        }
+
+        self.write(":")?;
+        self.write(&self.space())?;
+        value.serialize(&mut **self)?;
+        self.write(",")?;
+        self.write(&self.new_line())?;
+        Ok(())
+    }
+
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<()>
+        where K: ?Sized + Serialize,
+              V: ?Sized + Serialize
+    {
+        self.serialize_key(key)?;
+
+        if self.pretty.0.sort_map_keys {
+            return self.serialize_value(value);
+        }
+
+        self.write(":")?;
+        self.write(&self.space())?;
         value.serialize(&mut **self)?;
-        self.output += ",";
-        self.output += &self.new_line();
+        self.write(",")?;
+        self.write(&self.new_line())?;
         Ok(())
     }
 
     fn end(self) -> Result<()> {
-        self.end_indent();
+        if self.pretty.0.sort_map_keys {
+            let mut entries = self.sorted_maps.pop().map(|map| map.entries).unwrap_or_default();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+            for (key, value) in entries {
+                self.indent()?;
+                self.write(&key)?;
+                self.write(":")?;
+                self.write(&self.space())?;
+                self.write(&value)?;
+                self.write(",")?;
+                self.write(&self.new_line())?;
+            }
+        }
+
+        self.end_indent()?;
 
-        self.output += "}";
+        self.write("}")?;
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl<W: fmt::Write> ser::SerializeStruct for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
     fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
         where T: ?Sized + Serialize
     {
-        self.indent();
+        self.indent()?;
+        self.field_comment(key)?;
 
-        self.output += key;
-        self.output += ":";
-        self.output += &self.space();
+        // Written along with the start of the value
+        self.pending_field = Some(key);
         value.serialize(&mut **self)?;
-        self.output += ",";
-        self.output += &self.new_line();
+        self.write(",")?;
+        self.write(&self.new_line())?;
         Ok(())
     }
 
     fn end(self) -> Result<()> {
-        self.end_indent();
+        if ::std::mem::replace(&mut self.omitted_parens, false) {
+            return Ok(());
+        }
+
+        self.end_indent()?;
 
-        self.output += ")";
+        self.end_compound()?;
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl<W: fmt::Write> ser::SerializeStructVariant for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -594,7 +1285,7 @@ mod tests {
     #[derive(Serialize)]
     struct EmptyStruct2 {}
 
-    #[derive(Serialize)]
+    #[derive(Clone, Copy, Serialize)]
     struct MyStruct { x: f32, y: f32 }
 
     #[derive(Serialize)]
@@ -611,6 +1302,46 @@ mod tests {
         assert_eq!(to_string(&EmptyStruct2 {}).unwrap(), "()");
     }
 
+    #[test]
+    fn test_omit_struct_parens() {
+        #[derive(Serialize)]
+        struct Wrapper {
+            empty: EmptyStruct2,
+            list: Vec<EmptyStruct2>,
+        }
+
+        let value = Wrapper { empty: EmptyStruct2 {}, list: vec![EmptyStruct2 {}] };
+
+        let config = PrettyConfig::default_with(|x| x.new_line = "\n".to_string());
+        assert_eq!(to_string_pretty(&value, config.clone()).unwrap(), "\
+Wrapper(
+    empty: EmptyStruct2(
+    ),
+    list: [
+        EmptyStruct2(
+        ),
+    ],
+)");
+
+        let mut omitting = config;
+        omitting.omit_struct_parens_when_no_fields = true;
+        assert_eq!(to_string_pretty(&value, omitting).unwrap(), "\
+Wrapper(
+    empty: EmptyStruct2,
+    list: [
+        EmptyStruct2,
+    ],
+)");
+
+        let mut compact = PrettyConfig::basic(true);
+        compact.omit_struct_parens_when_no_fields = true;
+        assert_eq!(to_string_pretty(&value, compact).unwrap(), "Wrapper(empty:EmptyStruct2,list:[EmptyStruct2,],)");
+
+        let mut nameless = PrettyConfig::basic(false);
+        nameless.omit_struct_parens_when_no_fields = true;
+        assert_eq!(to_string_pretty(&value, nameless).unwrap(), "(empty:(),list:[(),],)");
+    }
+
     #[test]
     fn test_struct() {
         let my_struct = MyStruct { x: 4.0, y: 7.0 };
@@ -629,12 +1360,81 @@ mod tests {
         assert_eq!(to_string(&TupleStruct(2.0, 5.0)).unwrap(), "(2,5,)");
     }
 
+    #[test]
+    fn test_to_bytes() {
+        let my_struct = MyStruct { x: 4.0, y: 7.0 };
+
+        assert_eq!(to_bytes(&my_struct).unwrap(), b"(x:4,y:7,)".to_vec());
+        assert_eq!(to_bytes(&"é").unwrap(), to_string(&"é").unwrap().into_bytes());
+    }
+
     #[test]
     fn test_option() {
         assert_eq!(to_string(&Some(1u8)).unwrap(), "Some(1)");
         assert_eq!(to_string(&None::<u8>).unwrap(), "None");
     }
 
+    #[test]
+    fn test_implicit_some() {
+        let config = PrettyConfig::basic(false).with_extensions(Extensions::IMPLICIT_SOME);
+
+        assert_eq!(to_string_pretty(&Some(42u32), config.clone()).unwrap(),
+                   "#![enable(implicit_some)]42");
+        assert_eq!(to_string_pretty(&None::<u32>, config.clone()).unwrap(),
+                   "#![enable(implicit_some)]None");
+        assert_eq!(to_string_pretty(&vec![Some('a'), None], config).unwrap(),
+                   "#![enable(implicit_some)]['a',None,]");
+    }
+
+    #[test]
+    fn test_unwrap_newtypes() {
+        #[derive(Serialize)]
+        struct Meters(f64);
+
+        #[derive(Serialize)]
+        struct Name(&'static str);
+
+        #[derive(Serialize)]
+        struct Wrapper(MyStruct);
+
+        let config = PrettyConfig::basic(true).with_extensions(Extensions::UNWRAP_NEWTYPES);
+        let header = "#![enable(unwrap_newtypes)]";
+
+        assert_eq!(to_string_pretty(&Meters(4.2), config.clone()).unwrap(),
+                   format!("{}4.2", header));
+        assert_eq!(to_string_pretty(&Name("RON"), config.clone()).unwrap(),
+                   format!("{}\"RON\"", header));
+        assert_eq!(to_string_pretty(&Wrapper(MyStruct { x: 1.0, y: 2.0 }), config).unwrap(),
+                   format!("{}MyStruct(x:1,y:2,)", header));
+    }
+
+    #[test]
+    fn test_unwrap_variant_newtypes() {
+        #[derive(Serialize)]
+        struct NewType(i32);
+
+        #[derive(Serialize)]
+        enum Wrapping {
+            Tuple((bool,)),
+            Struct(MyStruct),
+            NewType(NewType),
+            Option(Option<MyStruct>),
+        }
+
+        let config = PrettyConfig::basic(true)
+            .with_extensions(Extensions::UNWRAP_VARIANT_NEWTYPES);
+        let ser = |value: &Wrapping| {
+            let s = to_string_pretty(value, config.clone()).unwrap();
+            s["#![enable(unwrap_variant_newtypes)]".len()..].to_string()
+        };
+
+        let my_struct = MyStruct { x: 1.0, y: 2.0 };
+        assert_eq!(ser(&Wrapping::Tuple((true,))), "Tuple(true,)");
+        assert_eq!(ser(&Wrapping::Struct(my_struct)), "Struct(x:1,y:2,)");
+        assert_eq!(ser(&Wrapping::NewType(NewType(3))), "NewType(3)");
+        assert_eq!(ser(&Wrapping::Option(Some(my_struct))), "Option(Some(MyStruct(x:1,y:2,)))");
+    }
+
     #[test]
     fn test_enum() {
         assert_eq!(to_string(&MyEnum::A).unwrap(), "A");
@@ -700,14 +1500,435 @@ mod tests {
     }
 
 
+    #[test]
+    fn test_struct_names() {
+        let nl = PrettyConfig::default().new_line;
+
+        let my_struct = MyStruct { x: 4.0, y: 7.0 };
+        assert_eq!(to_string_with_struct_names(&my_struct).unwrap(),
+                   format!("MyStruct({0}    x: 4,{0}    y: 7,{0})", nl));
+        assert_eq!(to_string_without_struct_names(&my_struct).unwrap(),
+                   format!("({0}    x: 4,{0}    y: 7,{0})", nl));
+
+        // Variant names are always emitted
+        let my_enum = MyEnum::D { a: 2, b: 3 };
+        let expected = format!("D({0}    a: 2,{0}    b: 3,{0})", nl);
+        assert_eq!(to_string_with_struct_names(&my_enum).unwrap(), expected);
+        assert_eq!(to_string_without_struct_names(&my_enum).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_sorted_maps() {
+        use std::collections::HashMap;
+
+        let mut a = HashMap::new();
+        let mut b = HashMap::new();
+        for i in 0..20 {
+            a.insert(format!("key{}", i), vec![i]);
+            b.insert(format!("key{}", 19 - i), vec![19 - i]);
+        }
+
+        let sorted = to_string_sorted(&a).unwrap();
+        assert_eq!(sorted, to_string_sorted(&b).unwrap());
+        assert!(sorted.starts_with("{\"key0\":[0,],\"key1\":[1,],\"key10\":[10,],"));
+
+        let mut nested = HashMap::new();
+        nested.insert('b', vec![("y", 2)].into_iter().collect::<HashMap<_, _>>());
+        nested.insert('a', vec![("z", 3), ("x", 1)].into_iter().collect());
+
+        let config = PrettyConfig::default_with(|x| x.new_line = "\n".to_string());
+        assert_eq!(to_string_pretty_sorted(&nested, config).unwrap(), "\
+{
+    'a': {
+        \"x\": 1,
+        \"z\": 3,
+    },
+    'b': {
+        \"y\": 2,
+    },
+}");
+    }
+
+    #[test]
+    fn test_sorted_collect_map() {
+        use serde::ser::{SerializeMap, Serializer as Serializer_};
+        use std::collections::HashMap;
+
+        /// Serializes its entries one by one instead of through `collect_map`
+        struct Entries(Vec<((u8, char), MyStruct)>);
+
+        impl Serialize for Entries {
+            fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+                where S: Serializer_
+            {
+                let mut map = serializer.serialize_map(Some(self.0.len()))?;
+                for (key, value) in &self.0 {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+
+        let entries = vec![
+            ((2, 'b'), MyStruct { x: 1.0, y: 2.0 }),
+            ((1, 'z'), MyStruct { x: 3.0, y: 4.0 }),
+            ((2, 'a'), MyStruct { x: 5.0, y: 6.0 }),
+        ];
+        let map: HashMap<_, _> = entries.iter().cloned().collect();
+        let config = PrettyConfig::default_with(|x| {
+            x.new_line = "\n".to_string();
+            x.sort_map_keys = true;
+        });
+
+        let collected = to_string_pretty(&map, config.clone()).unwrap();
+        assert_eq!(collected, to_string_pretty(&Entries(entries), config).unwrap());
+        assert_eq!(collected, "\
+{
+    (1, 'z',): MyStruct(
+        x: 3,
+        y: 4,
+    ),
+    (2, 'a',): MyStruct(
+        x: 5,
+        y: 6,
+    ),
+    (2, 'b',): MyStruct(
+        x: 1,
+        y: 2,
+    ),
+}");
+
+        let map: HashMap<_, _> = vec![(1, 'a')].into_iter().collect();
+        assert_eq!(to_string(&map).unwrap(), "{1:'a',}");
+        assert_eq!(to_string_sorted(&HashMap::<u8, u8>::new()).unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_map_entry() {
+        use serde::ser::{SerializeMap, Serializer as Serializer_};
+        use std::collections::BTreeMap;
+
+        struct Entries;
+
+        impl Serialize for Entries {
+            fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+                where S: Serializer_
+            {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("a", &1)?;
+                map.serialize_entry(&(true, false), &MyEnum::B(false))?;
+                map.end()
+            }
+        }
+
+        assert_eq!(to_string(&Entries).unwrap(), "{\"a\":1,(true,false,):B(false),}");
+
+        struct Reversed;
+
+        impl Serialize for Reversed {
+            fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+                where S: Serializer_
+            {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("b", &2)?;
+                map.serialize_entry("a", &[1])?;
+                map.end()
+            }
+        }
+
+        assert_eq!(to_string(&Reversed).unwrap(), "{\"b\":2,\"a\":(1,),}");
+        assert_eq!(to_string_sorted(&Reversed).unwrap(), "{\"a\":(1,),\"b\":2,}");
+
+        let config = PrettyConfig::default_with(|x| x.new_line = "\n".to_string());
+        assert_eq!(to_string_pretty(&Entries, config.clone()).unwrap(),
+                   "{\n    \"a\": 1,\n    (true, false,): B(false),\n}");
+
+        let mut map = BTreeMap::new();
+        map.insert(1, 'a');
+        map.insert(2, 'b');
+        assert_eq!(to_string_pretty(&map, config).unwrap(), "{\n    1: 'a',\n    2: 'b',\n}");
+    }
+
+    #[test]
+    fn test_tuple_spacing() {
+        let config = PrettyConfig::default();
+        assert_eq!(to_string_pretty(&("a ", "b "), config.clone()).unwrap(), "(\"a \", \"b \",)");
+        assert_eq!(to_string_pretty(&((1, 2), (3,), ()), config.clone()).unwrap(), "((1, 2,), (3,), (),)");
+        assert_eq!(to_string_pretty(&(1, vec![(2, 3)], 4), config).unwrap(), "(1, [\n    (2, 3,),\n], 4,)");
+
+        let config = PrettyConfig::default_with(|x| x.add_space = false);
+        assert_eq!(to_string_pretty(&("a ", (1, 2)), config).unwrap(), "(\"a \",(1,2,),)");
+    }
+
+    #[test]
+    fn test_inline_tuples_shorter_than() {
+        let config = PrettyConfig::default_with(|x| {
+            x.new_line = "\n".to_string();
+            x.separate_tuple_members = true;
+            x.inline_tuples_shorter_than = Some(3);
+        });
+
+        assert_eq!(to_string_pretty(&(1,), config.clone()).unwrap(), "(1,)");
+        assert_eq!(to_string_pretty(&(1, 2), config.clone()).unwrap(), "(1, 2,)");
+        assert_eq!(to_string_pretty(&(1, 2, 3), config.clone()).unwrap(),
+                   "(\n    1,\n    2,\n    3,\n)");
+        assert_eq!(to_string_pretty(&(1, 2, 3, 4), config.clone()).unwrap(),
+                   "(\n    1,\n    2,\n    3,\n    4,\n)");
+
+        assert_eq!(to_string_pretty(&(1, (2, 3), 4), config).unwrap(),
+                   "(\n    1,\n    (2, 3,),\n    4,\n)");
+    }
+
+
+    fn commented(style: CommentStyle) -> PrettyConfig {
+        let mut comments = BTreeMap::new();
+        comments.insert("x".to_string(), "horizontal".to_string());
+        comments.insert("y".to_string(), "vertical\nin pixels".to_string());
+
+        PrettyConfig::default_with(|x| {
+            x.new_line = "\n".to_string();
+            x.comment_style = style;
+            x.field_comments = Some(comments.clone());
+        })
+    }
+
+    #[test]
+    fn test_line_comments() {
+        let my_struct = MyStruct { x: 4.0, y: 7.0 };
+
+        assert_eq!(to_string_pretty(&my_struct, commented(CommentStyle::Line)).unwrap(), "\
+MyStruct(
+    // horizontal
+    x: 4,
+    // vertical
+    // in pixels
+    y: 7,
+)");
+
+        let mut compact = PrettyConfig::basic(false);
+        compact.field_comments = commented(CommentStyle::Line).field_comments;
+        assert_eq!(to_string_pretty(&my_struct, compact).unwrap(),
+                   "(// horizontal\nx:4,// vertical\n// in pixels\ny:7,)");
+    }
+
+    #[test]
+    fn test_variant_index_comments() {
+        #[derive(Serialize)]
+        struct Shapes {
+            first: Shape,
+            rest: Vec<Shape>,
+        }
+
+        #[derive(Serialize)]
+        enum Shape {
+            Dot,
+            Circle(f32),
+            Rect(u32, u32),
+            Poly { n: u8 },
+        }
+
+        let shapes = Shapes {
+            first: Shape::Dot,
+            rest: vec![Shape::Circle(0.5), Shape::Rect(2, 3), Shape::Poly { n: 5 }],
+        };
+
+        let config = PrettyConfig::default_with(|x| {
+            x.new_line = "\n".to_string();
+            x.emit_variant_index_comment = true;
+        });
+        assert_eq!(to_string_pretty(&shapes, config).unwrap(), "\
+Shapes(
+    // index 0
+    first: Dot,
+    rest: [
+        // index 1
+        Circle(0.5),
+        // index 2
+        Rect(2, 3,),
+        // index 3
+        Poly(
+            n: 5,
+        ),
+    ],
+)");
+
+        let mut compact = PrettyConfig::basic(false);
+        compact.emit_variant_index_comment = true;
+        assert_eq!(to_string_pretty(&shapes, compact).unwrap(),
+                   "(first:Dot,rest:[Circle(0.5),Rect(2,3,),Poly(n:5,),],)");
+    }
+
+    #[test]
+    fn test_annotations() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Numbers {
+            a: i8,
+            b: u64,
+            c: f32,
+            d: f64,
+            e: bool,
+            f: Vec<u16>,
+        }
+
+        let numbers = Numbers { a: -4, b: 1 << 40, c: 0.5, d: 2.0, e: true, f: vec![1, 2] };
+        let with = |style| {
+            let mut config = PrettyConfig::basic(false);
+            config.annotation_style = style;
+            to_string_pretty(&numbers, config).unwrap()
+        };
+
+        let plain = with(AnnotationStyle::None);
+        assert_eq!(plain, "(a:-4,b:1099511627776,c:0.5,d:2,e:true,f:[1,2,],)");
+        let suffixed = with(AnnotationStyle::Suffix);
+        assert_eq!(suffixed, "(a:-4i8,b:1099511627776u64,c:0.5f32,d:2f64,e:true,f:[1u16,2u16,],)");
+        let coerced = with(AnnotationStyle::CoercedSuffix);
+        assert_eq!(coerced, "(a:-4i8,b:1099511627776u64,c:0.5f32,d:2f64,e:true:bool,f:[1u16,2u16,],)");
+
+        for s in &[plain, suffixed, coerced] {
+            assert_eq!(::de::from_str::<Numbers>(s).as_ref(), Ok(&numbers), "{}", s);
+        }
+
+        let mut config = PrettyConfig::basic(false);
+        config.annotation_style = AnnotationStyle::CoercedSuffix;
+        let map: BTreeMap<bool, f64> = vec![(false, f64::INFINITY), (true, -1.5)].into_iter().collect();
+        let s = to_string_pretty(&map, config.clone()).unwrap();
+        assert_eq!(s, "{false:inf,true:-1.5f64,}");
+        assert_eq!(to_string_pretty(&map, config.with_sort_map_keys(true)).unwrap(), s);
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let my_struct = MyStruct { x: 4.0, y: 7.0 };
+
+        assert_eq!(to_string_pretty(&my_struct, commented(CommentStyle::Block)).unwrap(), "\
+MyStruct(
+    /* horizontal */
+    x: 4,
+    /* vertical
+in pixels */
+    y: 7,
+)");
+
+        let mut compact = PrettyConfig::basic(false);
+        compact.comment_style = CommentStyle::Block;
+        compact.field_comments = commented(CommentStyle::Line).field_comments;
+        assert_eq!(to_string_pretty(&MyEnum::D { a: 1, b: 2 }, compact).unwrap(), "D(a:1,b:2,)");
+
+        let mut config = commented(CommentStyle::Block);
+        config.field_comments = Some(vec![
+            ("x".to_string(), "opens /* here".to_string()),
+            ("y".to_string(), "closes */ and /*/ reopens".to_string()),
+        ].into_iter().collect());
+        let s = to_string_pretty(&my_struct, config).unwrap();
+        assert_eq!(s, "\
+MyStruct(
+    /* opens / * here */
+    x: 4,
+    /* closes * / and / * / reopens */
+    y: 7,
+)");
+        assert_eq!(::de::from_str::<::value::Value>(&s), ::de::from_str("MyStruct(x: 4, y: 7)"));
+    }
+
+    #[test]
+    fn test_to_string_fixed() {
+        let mut buf = [0; 32];
+        let len = to_string_fixed(&MyStruct { x: 4.0, y: 7.0 }, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"(x:4,y:7,)");
+
+        let mut buf = [0; 10];
+        assert_eq!(to_string_fixed(&MyStruct { x: 4.0, y: 7.0 }, &mut buf), Ok(10));
+
+        let mut buf = [0; 9];
+        assert_eq!(to_string_fixed(&MyStruct { x: 4.0, y: 7.0 }, &mut buf),
+                   Err(Error::Message("the output does not fit into the buffer".to_owned())));
+    }
+
+    #[test]
+    fn test_write_to_fmt() {
+        struct Ron<T>(T, PrettyConfig);
+
+        impl<T: Serialize> Display for Ron<T> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult {
+                write_to_fmt(&self.0, f, &self.1)
+            }
+        }
+
+        let my_struct = MyStruct { x: 4.0, y: 7.0 };
+        assert_eq!(Ron(my_struct, PrettyConfig::basic(true)).to_string(), "MyStruct(x:4,y:7,)");
+        assert_eq!(format!("value: {}", Ron((1, "a"), PrettyConfig::default())),
+                   "value: (1, \"a\",)");
+
+        let config = PrettyConfig::default().with_extensions(Extensions::IMPLICIT_SOME);
+        assert_eq!(Ron(Some(MyEnum::B(true)), config.clone()).to_string(),
+                   to_string_pretty(&Some(MyEnum::B(true)), config).unwrap());
+    }
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(format!("{}", Error::Message("oops".into())), "oops");
+        assert_eq!(Error::Message("oops".into()), ser::Error::custom("oops"));
+    }
+
+    #[test]
+    fn test_pretty_config_hash() {
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        assert!(set.insert(PrettyConfig::default()));
+        assert!(set.insert(PrettyConfig::basic(false)));
+        assert!(!set.insert(PrettyConfig::default()));
+        assert!(!set.insert(PrettyConfig::basic(false)));
+        assert!(set.insert(PrettyConfig::basic(true)));
+        assert_eq!(set.len(), 3);
+    }
+
     #[test]
     fn test_string() {
         assert_eq!(to_string(&"Some string").unwrap(), "\"Some string\"");
     }
 
+    #[test]
+    fn test_raw_string() {
+        assert_eq!(to_string(&RawStr("C:\\Users")).unwrap(), "r\"C:\\Users\"");
+        assert_eq!(to_string(&RawStr("say \"hi\"")).unwrap(), "r#\"say \"hi\"\"#");
+        assert_eq!(to_string(&RawStr("\"#\" and \"##")).unwrap(), "r###\"\"#\" and \"##\"###");
+        assert_eq!(to_string(&vec![RawStr("a"), RawStr("b")]).unwrap(), "[r\"a\",r\"b\",]");
+        assert_eq!(to_string(&(RawStr("\\d+"), "\\d+")).unwrap(), "(r\"\\d+\",\"\\\\d+\",)");
+
+        let regex = "^\"(\\\\|[^\"#])*\"#?$";
+        let mut s = Serializer::new(String::new(), PrettyConfig::basic(false)).unwrap();
+        s.serialize_str_raw(regex).unwrap();
+        assert_eq!(::de::from_str::<String>(&s.output).unwrap(), regex);
+    }
+
+    #[test]
+    fn test_serialize_comment() {
+        let mut s = Serializer::new(String::new(), PrettyConfig::default()).unwrap();
+        s.serialize_comment("two\nlines").unwrap();
+        ().serialize(&mut s).unwrap();
+        assert_eq!(s.output, "// two\n// lines\n()");
+
+        let mut s = Serializer::new(String::new(), PrettyConfig::basic(false)).unwrap();
+        s.serialize_comment("dropped").unwrap();
+        "x".serialize(&mut s).unwrap();
+        assert_eq!(s.output, "\"x\"");
+    }
+
+    #[test]
+    fn test_commented() {
+        assert_eq!(to_string_pretty(&Commented(42, "the answer"), PrettyConfig::default()).unwrap(),
+                   "// the answer\n42");
+        assert_eq!(to_string(&Commented(42, "the answer")).unwrap(), "42");
+    }
+
     #[test]
     fn test_char() {
         assert_eq!(to_string(&'c').unwrap(), "'c'");
+        assert_eq!(to_string(&'\n').unwrap(), "'\\n'");
+        assert_eq!(to_string(&'\x1b').unwrap(), "'\\u{1b}'");
+        assert_eq!(to_string(&'\u{85}').unwrap(), "'\\u{85}'");
+        assert_eq!(to_string(&'"').unwrap(), "'\"'");
     }
 
     #[test]
@@ -4,6 +4,8 @@ use super::{Result, to_string_pretty};
 
 use serde::ser::Serialize;
 use std::default::Default;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
 
 /// Serializes `value` in the recommended RON layout with
 /// default pretty configuration.
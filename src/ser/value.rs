@@ -1,16 +1,19 @@
-use serde::ser::{Serialize, Serializer};
+//! Serializing Rust values into `Value` trees.
 
-use value::Value;
+use serde::ser::{self, Serialize, Serializer};
+
+use ser::{Error, Result};
+use value::{Map, Number, Value};
 
 impl Serialize for Value {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
         where
             S: Serializer
     {
         match *self {
             Value::Bool(b) => serializer.serialize_bool(b),
             Value::Char(c) => serializer.serialize_char(c),
-            Value::Map(ref m) => Serialize::serialize(m, serializer),
+            Value::Map(ref m) => serializer.collect_map(m),
             Value::Number(ref n) => serializer.serialize_f64(n.get()),
             Value::Option(Some(ref o)) => serializer.serialize_some(o.as_ref()),
             Value::Option(None) => serializer.serialize_none(),
@@ -20,3 +23,449 @@ impl Serialize for Value {
         }
     }
 }
+
+/// Converts `value` into a `Value` tree.
+///
+/// Enum variants are represented the way `de::from_value` expects them:
+/// a unit variant as a string with its name, any other variant as a map
+/// with a single entry from its name to its content.
+pub fn to_ron_value<T>(value: &T) -> Result<Value>
+    where T: Serialize + ?Sized
+{
+    value.serialize(ValueSerializer)
+}
+
+/// A serializer building a `Value` tree, see `to_ron_value`.
+pub struct ValueSerializer;
+
+fn number<N: Into<f64>>(n: N) -> Result<Value> {
+    Ok(Value::Number(Number::new(n.into())))
+}
+
+fn variant(name: &str, content: Value) -> Value {
+    Value::Map(Map::from_str_keys(vec![(name, content)]))
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeVec;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeMap;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        number(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        number(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        number(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        number(v as f64)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value> {
+        number(v as f64)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        number(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        number(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        number(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        number(v as f64)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value> {
+        number(v as f64)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        number(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        number(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::Char(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(Value::Seq(v.iter().map(|&b| Value::Number(Number::new(b.into()))).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Option(None))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Value>
+        where T: ?Sized + Serialize
+    {
+        Ok(Value::Option(Some(Box::new(value.serialize(self)?))))
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Unit)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Value> {
+        Ok(Value::Unit)
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<Value> {
+        Ok(Value::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<Value>
+        where T: ?Sized + Serialize
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T
+    ) -> Result<Value>
+        where T: ?Sized + Serialize
+    {
+        Ok(self::variant(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec> {
+        Ok(SerializeVec {
+            vec: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<SerializeVec> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize
+    ) -> Result<SerializeVec> {
+        Ok(SerializeVec {
+            vec: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap> {
+        Ok(SerializeMap {
+            map: Map::with_capacity(len.unwrap_or(0)),
+            next_key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<SerializeMap> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize
+    ) -> Result<SerializeMap> {
+        Ok(SerializeMap {
+            map: Map::with_capacity(len),
+            next_key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+/// Builds a `Value::Seq` for sequences, tuples and tuple variants.
+pub struct SerializeVec {
+    vec: Vec<Value>,
+    /// The name of the tuple variant, if any
+    variant: Option<&'static str>,
+}
+
+impl SerializeVec {
+    fn push<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + Serialize
+    {
+        self.vec.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value> {
+        let seq = Value::Seq(self.vec);
+        Ok(match self.variant {
+            Some(name) => variant(name, seq),
+            None => seq,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + Serialize
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + Serialize
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + Serialize
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVec {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + Serialize
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+/// Builds a `Value::Map` for maps, structs and struct variants.
+pub struct SerializeMap {
+    map: Map,
+    /// The key passed to `serialize_key`, waiting for its value
+    next_key: Option<Value>,
+    /// The name of the struct variant, if any
+    variant: Option<&'static str>,
+}
+
+impl SerializeMap {
+    fn finish(self) -> Result<Value> {
+        let map = Value::Map(self.map);
+        Ok(match self.variant {
+            Some(name) => variant(name, map),
+            None => map,
+        })
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+        where T: ?Sized + Serialize
+    {
+        self.next_key = Some(key.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + Serialize
+    {
+        let key = self.next_key.take()
+            .ok_or_else(|| Error::Message("serialize_value called before serialize_key".to_owned()))?;
+        self.map.insert(key, value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+        where T: ?Sized + Serialize
+    {
+        self.map.insert(Value::String(key.to_owned()), value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+        where T: ?Sized + Serialize
+    {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use de::from_value;
+    use ser::to_ron_value;
+    use value::{Map, Number, Value};
+
+    fn json(v: &Value) -> String {
+        serde_json::to_string(v).expect("Failed to serialize")
+    }
+
+    #[test]
+    fn test_primitives() {
+        assert_eq!(json(&Value::Bool(true)), "true");
+        assert_eq!(json(&Value::Char('c')), "\"c\"");
+        assert_eq!(json(&Value::Number(Number::new(1.5))), "1.5");
+        assert_eq!(json(&Value::String("Hello".to_owned())), "\"Hello\"");
+        assert_eq!(json(&Value::Unit), "null");
+    }
+
+    #[test]
+    fn test_option() {
+        assert_eq!(json(&Value::Option(None)), "null");
+        assert_eq!(json(&Value::Option(Some(Box::new(Value::Bool(false))))), "false");
+    }
+
+    #[test]
+    fn test_containers() {
+        let seq = Value::Seq(vec![Value::Bool(true), Value::Unit]);
+        assert_eq!(json(&seq), "[true,null]");
+
+        let map = Value::Map(vec![
+            (Value::String("a".to_owned()), Value::Number(Number::new(0.5))),
+            (Value::String("b".to_owned()), seq),
+        ].into_iter().collect());
+        assert_eq!(json(&map), "{\"a\":0.5,\"b\":[true,null]}");
+    }
+
+    #[test]
+    fn test_from_ron() {
+        let value = Value::from_str("Room(width: 20.5, name: \"The Room\")").unwrap();
+        assert_eq!(json(&value), "{\"width\":20.5,\"name\":\"The Room\"}");
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct MyStruct { x: f32, y: f32 }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum MyEnum {
+        A,
+        B(bool),
+        C(bool, f32),
+        D { a: i32, b: i32 },
+    }
+
+    fn num(n: f64) -> Value {
+        Value::Number(Number::new(n))
+    }
+
+    #[test]
+    fn test_to_ron_value() {
+        assert_eq!(to_ron_value(&MyStruct { x: 4.0, y: 7.0 }),
+                   Ok(Value::Map(Map::from_str_keys(vec![("x", num(4.0)), ("y", num(7.0))]))));
+        assert_eq!(to_ron_value(&MyEnum::D { a: 2, b: 3 }),
+                   Ok(Value::Map(Map::from_str_keys(vec![
+                       ("D", Value::Map(Map::from_str_keys(vec![("a", num(2.0)), ("b", num(3.0))]))),
+                   ]))));
+        assert_eq!(to_ron_value(&MyEnum::A), Ok(Value::String("A".to_owned())));
+        assert_eq!(to_ron_value(&MyEnum::C(true, 0.5)),
+                   Ok(Value::Map(Map::from_str_keys(vec![("C", Value::Seq(vec![Value::Bool(true), num(0.5)]))]))));
+        assert_eq!(to_ron_value(&(Some('a'), None::<u8>, ())),
+                   Ok(Value::Seq(vec![
+                       Value::Option(Some(Box::new(Value::Char('a')))),
+                       Value::Option(None),
+                       Value::Unit,
+                   ])));
+        assert_eq!(to_ron_value(&vec![(1, "one")].into_iter().collect::<::std::collections::BTreeMap<_, _>>()),
+                   Ok(Value::Map(vec![(num(1.0), Value::String("one".to_owned()))].into_iter().collect())));
+    }
+
+    #[test]
+    fn test_value_roundtrip() {
+        let structs = vec![MyStruct { x: 4.0, y: 7.0 }, MyStruct { x: -0.5, y: 1e10 }];
+        assert_eq!(from_value(&to_ron_value(&structs).unwrap()), Ok(structs));
+
+        let enums = vec![MyEnum::A, MyEnum::B(false), MyEnum::C(true, 2.5), MyEnum::D { a: 2, b: -3 }];
+        assert_eq!(from_value(&to_ron_value(&enums).unwrap()), Ok(enums));
+
+        let value = Value::from_str("(a: [1, 'b', \"c\"], d: Some({(): None}))").unwrap();
+        assert_eq!(to_ron_value(&value), Ok(value));
+    }
+}
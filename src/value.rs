@@ -1,22 +1,27 @@
 //! Value module.
 
 use std::cmp::{Eq, Ordering};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Add, Div, Mul, Sub};
+use std::slice;
+use std::vec;
 use std::hash::{Hash, Hasher};
 
-/// A wrapper for `f64` which guarantees that the inner value
-/// is finite and thus implements `Eq`, `Hash` and `Ord`.
-#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
+/// A wrapper for `f64` which implements `Eq`, `Hash` and `Ord`.
+///
+/// Numbers are compared numerically, with NaN being greater than
+/// every other number (including infinity) and equal to itself.
+#[derive(Copy, Clone, Debug)]
 pub struct Number(f64);
 
 impl Number {
-    /// Panics if `v` is not a real number
-    /// (infinity, NaN, ..).
+    /// Wraps any `f64`, including infinity and NaN.
+    ///
+    /// A NaN is kept as is and compares equal to other NaN `Number`s.
     pub fn new(v: f64) -> Self {
-        if !v.is_finite() {
-            panic!("Tried to create Number with a NaN / infinity");
-        }
-
         Number(v)
     }
 
@@ -24,6 +29,24 @@ impl Number {
     pub fn get(&self) -> f64 {
         self.0
     }
+
+    /// Returns `true` if the number is NaN.
+    ///
+    /// Unlike with `f64`, a NaN `Number` is equal to itself.
+    pub fn is_nan(&self) -> bool {
+        self.0.is_nan()
+    }
+
+    /// Returns `true` if the number is neither infinite nor NaN.
+    pub fn is_finite(&self) -> bool {
+        self.0.is_finite()
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
 }
 
 impl Eq for Number {}
@@ -34,9 +57,311 @@ impl Hash for Number {
     }
 }
 
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Ord for Number {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).expect("Bug: Contract violation")
+        self.0
+            .partial_cmp(&other.0)
+            .unwrap_or_else(|| self.is_nan().cmp(&other.is_nan()))
+    }
+}
+
+/// An error in arithmetic on `Number`s.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArithmeticError {
+    /// The divisor was zero
+    DivisionByZero,
+    /// The operands were finite, but the result is not
+    Overflow,
+}
+
+impl fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArithmeticError::DivisionByZero => f.write_str("division by zero"),
+            ArithmeticError::Overflow => f.write_str("arithmetic overflow"),
+        }
+    }
+}
+
+impl ::std::error::Error for ArithmeticError {}
+
+/// Implements an arithmetic operator for `Number`, resulting in an error
+/// instead of an infinity or NaN computed from finite operands.
+///
+/// As numbers are floats, integral operands give an integral result (except
+/// for division) as long as it is smaller than 2^53 in magnitude.
+macro_rules! number_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait for Number {
+            type Output = Result<Number, ArithmeticError>;
+
+            fn $method(self, rhs: Number) -> Self::Output {
+                let result = self.0 $op rhs.0;
+                if result.is_finite() || !self.is_finite() || !rhs.is_finite() {
+                    Ok(Number(result))
+                } else {
+                    Err(ArithmeticError::Overflow)
+                }
+            }
+        }
+    };
+}
+
+number_op!(Add, add, +);
+number_op!(Sub, sub, -);
+number_op!(Mul, mul, *);
+
+impl Div for Number {
+    type Output = Result<Number, ArithmeticError>;
+
+    fn div(self, rhs: Number) -> Self::Output {
+        if rhs.0 == 0.0 {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+
+        let result = self.0 / rhs.0;
+        if result.is_finite() || !self.is_finite() || !rhs.is_finite() {
+            Ok(Number(result))
+        } else {
+            Err(ArithmeticError::Overflow)
+        }
+    }
+}
+
+/// A map of values which remembers the order its keys were inserted in.
+///
+/// Like a `BTreeMap`, maps are compared and hashed by their entries sorted
+/// by key, so maps which only differ in the order of their entries are equal.
+/// To deserialize maps into statically typed keys and values, see
+/// `de::OwnedMap` and `de::BorrowedMap`.
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(Value, Value)>,
+    /// The position of each key in `entries`
+    index: BTreeMap<Value, usize>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    /// Creates an empty map with space for at least `n` entries.
+    pub fn with_capacity(n: usize) -> Self {
+        Map {
+            entries: Vec::with_capacity(n),
+            index: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a map with the given entries, in order.
+    ///
+    /// If a key occurs more than once, the last value wins,
+    /// but the key keeps the position of its first occurrence.
+    pub fn from_ordered_pairs<I>(pairs: I) -> Self
+        where I: IntoIterator<Item = (Value, Value)>
+    {
+        pairs.into_iter().collect()
+    }
+
+    /// Like `from_ordered_pairs`, but with string keys.
+    pub fn from_str_keys<'a, I>(pairs: I) -> Self
+        where I: IntoIterator<Item = (&'a str, Value)>
+    {
+        pairs
+            .into_iter()
+            .map(|(k, v)| (Value::String(k.to_owned()), v))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &Value) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn get_mut(&mut self, key: &Value) -> Option<&mut Value> {
+        match self.index.get(key) {
+            Some(&i) => Some(&mut self.entries[i].1),
+            None => None,
+        }
+    }
+
+    /// Inserts a value, returning the previous value of `key`.
+    ///
+    /// New keys are appended, existing keys keep their position.
+    pub fn insert(&mut self, key: Value, value: Value) -> Option<Value> {
+        if let Some(&i) = self.index.get(&key) {
+            return Some(mem::replace(&mut self.entries[i].1, value));
+        }
+
+        self.index.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+
+        None
+    }
+
+    /// Removes `key`, shifting all following entries to the front.
+    ///
+    /// To keep the insertion order, this takes O(n) time.
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        let removed = self.index.remove(key)?;
+
+        for i in self.index.values_mut() {
+            if *i > removed {
+                *i -= 1;
+            }
+        }
+
+        Some(self.entries.remove(removed).1)
+    }
+
+    /// Gets the entry of `key` for in-place manipulation.
+    pub fn entry(&mut self, key: Value) -> Entry<'_> {
+        Entry { map: self, key }
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        self.into_iter()
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &Value> + ExactSizeIterator {
+        self.entries.iter().map(|e| &e.0)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &Value> + ExactSizeIterator {
+        self.entries.iter().map(|e| &e.1)
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Value> + ExactSizeIterator {
+        self.entries.iter_mut().map(|e| &mut e.1)
+    }
+}
+
+impl Map {
+    /// Iterates over the entries sorted by key.
+    fn sorted<'a>(&'a self) -> impl Iterator<Item = (&'a Value, &'a Value)> + 'a {
+        self.index.iter().map(move |(k, &i)| (k, &self.entries[i].1))
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.sorted().eq(other.sorted())
+    }
+}
+
+impl Eq for Map {}
+
+impl PartialOrd for Map {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Map {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sorted().cmp(other.sorted())
+    }
+}
+
+impl Hash for Map {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for (k, v) in self.sorted() {
+            k.hash(state);
+            v.hash(state);
+        }
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<(Value, Value)> for Map {
+    fn from_iter<I>(iter: I) -> Self
+        where I: IntoIterator<Item = (Value, Value)>
+    {
+        let mut map = Map::new();
+        map.extend(iter);
+
+        map
+    }
+}
+
+impl Extend<(Value, Value)> for Map {
+    fn extend<I>(&mut self, iter: I)
+        where I: IntoIterator<Item = (Value, Value)>
+    {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+/// An iterator over the entries of a `Map`, in insertion order.
+pub type Iter<'a> = ::std::iter::Map<slice::Iter<'a, (Value, Value)>,
+                                     fn(&'a (Value, Value)) -> (&'a Value, &'a Value)>;
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = (&'a Value, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        fn split(e: &(Value, Value)) -> (&Value, &Value) {
+            (&e.0, &e.1)
+        }
+
+        self.entries.iter().map(split)
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (Value, Value);
+    type IntoIter = vec::IntoIter<(Value, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// A view into a single entry of a `Map`, created by `Map::entry`.
+pub struct Entry<'a> {
+    map: &'a mut Map,
+    key: Value,
+}
+
+impl<'a> Entry<'a> {
+    /// Returns the value of the entry, inserting `default` first if the key is absent.
+    pub fn or_insert(self, default: Value) -> &'a mut Value {
+        let i = match self.map.index.get(&self.key) {
+            Some(&i) => i,
+            None => {
+                self.map.insert(self.key, default);
+                self.map.entries.len() - 1
+            }
+        };
+
+        &mut self.map.entries[i].1
     }
 }
 
@@ -44,10 +369,1200 @@ impl Ord for Number {
 pub enum Value {
     Bool(bool),
     Char(char),
-    Map(BTreeMap<Value, Value>),
+    Map(Map),
     Number(Number),
     Option(Option<Box<Value>>),
     String(String),
     Seq(Vec<Value>),
     Unit,
 }
+
+impl Value {
+    /// Returns the name of the value's type, for messages like
+    /// "expected string, got integer".
+    ///
+    /// Numbers are called `"integer"` if they have no fractional part
+    /// and `"float"` otherwise.
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Value::Bool(_) => "bool",
+            Value::Char(_) => "char",
+            Value::Map(_) => "map",
+            Value::Number(ref n) if n.get().fract() == 0.0 => "integer",
+            Value::Number(_) => "float",
+            Value::Option(_) => "option",
+            Value::String(_) => "string",
+            Value::Seq(_) => "seq",
+            Value::Unit => "unit",
+        }
+    }
+
+    /// Returns the number of elements of a sequence or entries of a map,
+    /// or `None` if the value is not a container.
+    pub fn len(&self) -> Option<usize> {
+        match *self {
+            Value::Map(ref m) => Some(m.len()),
+            Value::Seq(ref s) => Some(s.len()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the value is an empty sequence or map.
+    ///
+    /// Values which are not containers are never empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Returns whether the value is a map containing the string `key`,
+    /// which for structs is a field name.
+    pub fn contains_key(&self, key: &str) -> bool {
+        match *self {
+            Value::Map(ref m) => m.contains_key(&Value::String(key.to_owned())),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the value is a sequence with an element at index `i`.
+    pub fn contains_index(&self, i: usize) -> bool {
+        match *self {
+            Value::Seq(ref s) => i < s.len(),
+            _ => false,
+        }
+    }
+
+    /// Inserts an entry into a map, returning the previous value of `key`.
+    ///
+    /// A new key is appended after the existing entries,
+    /// an existing one keeps its position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not a map.
+    pub fn insert(&mut self, key: Value, value: Value) -> Option<Value> {
+        match *self {
+            Value::Map(ref mut m) => m.insert(key, value),
+            _ => panic!("Value::insert called on a value which is not a map"),
+        }
+    }
+
+    /// Appends an element to a sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not a sequence.
+    pub fn push(&mut self, element: Value) {
+        match *self {
+            Value::Seq(ref mut s) => s.push(element),
+            _ => panic!("Value::push called on a value which is not a sequence"),
+        }
+    }
+
+    /// Removes the entry with the string `key` from a map, shifting the
+    /// following entries, and returns its value.
+    ///
+    /// Returns `None` if there is no such entry or the value is not a map.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        match *self {
+            Value::Map(ref mut m) => m.remove(&Value::String(key.to_owned())),
+            _ => None,
+        }
+    }
+
+    /// Removes the element at index `i` from a sequence, shifting the
+    /// following elements, and returns it.
+    ///
+    /// Returns `None` if `i` is out of bounds or the value is not a sequence.
+    pub fn remove_index(&mut self, i: usize) -> Option<Value> {
+        match *self {
+            Value::Seq(ref mut s) if i < s.len() => Some(s.remove(i)),
+            _ => None,
+        }
+    }
+
+    /// Removes the value at the dot-separated `path`, like `"logging.level"`,
+    /// from the tree and returns it.
+    ///
+    /// Each segment of the path is either the string key of a map entry,
+    /// which for structs is a field name, or the index of a sequence element.
+    /// `Some` values are looked through. Returns `None` if there is no value
+    /// at `path`, or if `path` is empty since the root can't be removed.
+    pub fn take(&mut self, path: &str) -> Option<Value> {
+        if path.is_empty() {
+            return None;
+        }
+
+        self.take_segments(path.split('.').collect())
+    }
+
+    /// Like `take`, but with a JSON Pointer like `"/logging/level"`,
+    /// in which `~1` stands for `/` and `~0` for `~`.
+    pub fn take_pointer(&mut self, pointer: &str) -> Option<Value> {
+        if !pointer.starts_with('/') {
+            return None;
+        }
+
+        let segments = pointer[1..]
+            .split('/')
+            .map(|s| s.replace("~1", "/").replace("~0", "~"))
+            .collect();
+
+        self.take_segments(segments)
+    }
+
+    fn take_segments<S>(&mut self, mut segments: Vec<S>) -> Option<Value>
+        where S: AsRef<str>
+    {
+        let last = segments.pop()?;
+        let mut parent = self;
+        for segment in &segments {
+            parent = parent.child_mut(segment.as_ref())?;
+        }
+
+        match *parent {
+            Value::Option(Some(ref mut v)) => v.take_segments(vec![last]),
+            Value::Seq(_) => parent.remove_index(last.as_ref().parse().ok()?),
+            _ => parent.remove(last.as_ref()),
+        }
+    }
+
+    fn child_mut(&mut self, segment: &str) -> Option<&mut Value> {
+        match *self {
+            Value::Map(ref mut m) => m.get_mut(&Value::String(segment.to_owned())),
+            Value::Option(Some(ref mut v)) => v.child_mut(segment),
+            Value::Seq(ref mut s) => s.get_mut(segment.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+
+    /// Flattens the tree into a map from dot-separated paths,
+    /// like `"server.address.port"`, to the leaves of the tree.
+    ///
+    /// Map keys which are strings, like the field names of structs, are used
+    /// as they are, other keys are written in RON notation. Sequence elements
+    /// are keyed by their index, so `(items: [a, b])` gives `"items.0"` and
+    /// `"items.1"`. Options, empty maps and sequences and all other values are
+    /// leaves; if the root itself is one, it is keyed by the empty string.
+    pub fn flatten(&self) -> HashMap<String, Value> {
+        let mut flat = HashMap::new();
+        self.flatten_into(String::new(), &mut flat);
+
+        flat
+    }
+
+    fn flatten_into(&self, path: String, flat: &mut HashMap<String, Value>) {
+        let child = |segment: &str| if path.is_empty() {
+            segment.to_owned()
+        } else {
+            format!("{}.{}", path, segment)
+        };
+
+        match *self {
+            Value::Map(ref m) if !m.is_empty() => for (k, v) in m {
+                v.flatten_into(child(&key_to_string(k)), flat);
+            },
+            Value::Seq(ref s) if !s.is_empty() => for (i, v) in s.iter().enumerate() {
+                v.flatten_into(child(&i.to_string()), flat);
+            },
+            _ => {
+                flat.insert(path, self.clone());
+            }
+        }
+    }
+
+    /// Rebuilds a tree from a map of dot-separated paths to leaves,
+    /// the inverse of `flatten`.
+    ///
+    /// Every path segment becomes a string key of a map, except that maps
+    /// whose keys are exactly `"0"`, `"1"`, ... are turned into sequences.
+    /// Map entries are sorted by key, so `unflatten(v.flatten())` gives back
+    /// `v` if its maps are sorted that way and only have string keys
+    /// without dots.
+    ///
+    /// Fails if a path is both a leaf and the prefix of another path,
+    /// like `"a"` and `"a.b"`.
+    pub fn unflatten(flat: HashMap<String, Value>) -> Result<Value, UnflattenError> {
+        if flat.contains_key("") {
+            return match flat.len() {
+                1 => Ok(flat.into_iter().next().unwrap().1),
+                _ => Err(UnflattenError { key: String::new() }),
+            };
+        }
+
+        let mut root = BTreeMap::new();
+        for (key, value) in flat {
+            let mut node = &mut root;
+            let mut segments = key.split('.').peekable();
+            let mut len = 0;
+
+            while let Some(segment) = segments.next() {
+                len += segment.len() + 1;
+                let conflict = || UnflattenError { key: key[..len - 1].to_owned() };
+
+                if segments.peek().is_none() {
+                    if node.insert(segment.to_owned(), FlatNode::Leaf(value)).is_some() {
+                        return Err(conflict());
+                    }
+                    break;
+                }
+
+                let child = node
+                    .entry(segment.to_owned())
+                    .or_insert_with(|| FlatNode::Branch(BTreeMap::new()));
+                node = match *child {
+                    FlatNode::Branch(ref mut children) => children,
+                    FlatNode::Leaf(_) => return Err(conflict()),
+                };
+            }
+        }
+
+        Ok(FlatNode::Branch(root).into_value())
+    }
+
+    /// Returns an iterator over the keys of a map, which for structs
+    /// are the field names, or `None` if the value is not a map.
+    pub fn keys(&self) -> Option<impl Iterator<Item = &Value>> {
+        match *self {
+            Value::Map(ref m) => Some(m.keys()),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the values of a map,
+    /// or `None` if the value is not a map.
+    pub fn values(&self) -> Option<impl Iterator<Item = &Value>> {
+        match *self {
+            Value::Map(ref m) => Some(m.values()),
+            _ => None,
+        }
+    }
+
+    /// Applies `f` to every map key in the tree, including
+    /// the field names of structs.
+    pub fn map_keys<F>(self, f: F) -> Value
+        where F: Fn(Value) -> Value
+    {
+        self.map_with(&|v| v, &f)
+    }
+
+    /// Applies `f` to every value in the tree which is neither a map key
+    /// nor a container (a sequence, a map or `Some`).
+    pub fn map_values<F>(self, f: F) -> Value
+        where F: Fn(Value) -> Value
+    {
+        let values = |v: Value| match v {
+            Value::Map(_) | Value::Seq(_) | Value::Option(Some(_)) => v,
+            v => f(v),
+        };
+
+        self.map_with(&values, &|k| k)
+    }
+
+    /// Applies `f` to every node of the tree, including map keys.
+    ///
+    /// Children are transformed before their parents.
+    pub fn map_all<F>(self, f: F) -> Value
+        where F: Fn(Value) -> Value
+    {
+        self.map_all_with(&f)
+    }
+
+    fn map_all_with(self, f: &dyn Fn(Value) -> Value) -> Value {
+        self.map_with(f, &|k| k.map_all_with(f))
+    }
+
+    /// Applies `values` to every node in post-order, except for map keys
+    /// which are passed to `keys` instead.
+    fn map_with(self, values: &dyn Fn(Value) -> Value, keys: &dyn Fn(Value) -> Value) -> Value {
+        let mapped = match self {
+            Value::Map(m) => Value::Map(m.into_iter()
+                .map(|(k, v)| (keys(k), v.map_with(values, keys)))
+                .collect()),
+            Value::Option(Some(v)) => Value::Option(Some(Box::new(v.map_with(values, keys)))),
+            Value::Seq(s) => Value::Seq(s.into_iter().map(|v| v.map_with(values, keys)).collect()),
+            v => v,
+        };
+
+        values(mapped)
+    }
+
+    /// Writes the differences between `a` and `b`, walking both trees at once.
+    ///
+    /// Each differing subtree is written as its path, like `/server/port`,
+    /// followed by the subtree of `a` prefixed with `- ` and the one of `b`
+    /// prefixed with `+ `. Map entries and sequence elements present on one
+    /// side only get a single line. Map entries are matched by key, so maps
+    /// which only differ in the order of their entries show no differences.
+    pub fn format_diff(a: &Value, b: &Value, f: &mut fmt::Formatter) -> fmt::Result {
+        a.diff_at(b, &mut String::new(), f)
+    }
+
+    fn diff_at(&self, other: &Value, path: &mut String, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Option(Some(ref a)) => {
+                if let Value::Option(Some(ref b)) = *other {
+                    return a.diff_at(b, path, f);
+                }
+            }
+            Value::Seq(ref a) => {
+                if let Value::Seq(ref b) = *other {
+                    for i in 0..a.len().max(b.len()) {
+                        with_segment(path, &i.to_string(), |path| diff_entry(a.get(i), b.get(i), path, f))?;
+                    }
+
+                    return Ok(());
+                }
+            }
+            Value::Map(ref a) => {
+                if let Value::Map(ref b) = *other {
+                    for (k, v) in a {
+                        with_segment(path, &key_to_string(k), |path| diff_entry(Some(v), b.get(k), path, f))?;
+                    }
+                    for (k, v) in b.iter().filter(|&(k, _)| !a.contains_key(k)) {
+                        with_segment(path, &key_to_string(k), |path| diff_entry(None, Some(v), path, f))?;
+                    }
+
+                    return Ok(());
+                }
+            }
+            _ => {}
+        }
+
+        if self == other {
+            Ok(())
+        } else {
+            write_change(Some(self), Some(other), path, f)
+        }
+    }
+
+    /// Checks this value against `schema`, returning all violations.
+    ///
+    /// An empty list means the value matches the schema.
+    pub fn validate(&self, schema: &Schema) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        self.validate_at(schema, &mut String::new(), &mut errors);
+
+        errors
+    }
+
+    fn validate_at(&self, schema: &Schema, path: &mut String, errors: &mut Vec<ValidationError>) {
+        let matched = match *schema {
+            Schema::Any => true,
+            Schema::Bool => matches!(*self, Value::Bool(_)),
+            Schema::Char => matches!(*self, Value::Char(_)),
+            Schema::String => matches!(*self, Value::String(_)),
+            Schema::Unit => matches!(*self, Value::Unit),
+            Schema::Number { min, max } => match *self {
+                Value::Number(n) => {
+                    let n = n.get();
+
+                    if min.is_some_and(|min| n < min) || max.is_some_and(|max| n > max) {
+                        let range = format!("{}..={}",
+                                            min.map(|m| m.to_string()).unwrap_or_default(),
+                                            max.map(|m| m.to_string()).unwrap_or_default());
+                        errors.push(ValidationError::new(path, format!("{} is out of range {}", n, range)));
+                    }
+
+                    true
+                }
+                _ => false,
+            },
+            Schema::Option(ref schema) => match *self {
+                Value::Option(Some(ref v)) => {
+                    v.validate_at(schema, path, errors);
+
+                    true
+                }
+                Value::Option(None) => true,
+                _ => false,
+            },
+            Schema::Seq(ref schema) => match *self {
+                Value::Seq(ref seq) => {
+                    for (i, v) in seq.iter().enumerate() {
+                        with_segment(path, &i.to_string(), |path| v.validate_at(schema, path, errors));
+                    }
+
+                    true
+                }
+                _ => false,
+            },
+            Schema::Map(ref keys, ref values) => match *self {
+                Value::Map(ref map) => {
+                    for (k, v) in map {
+                        with_segment(path, &key_to_string(k), |path| {
+                            k.validate_at(keys, path, errors);
+                            v.validate_at(values, path, errors);
+                        });
+                    }
+
+                    true
+                }
+                _ => false,
+            },
+            Schema::Struct(ref fields) => match *self {
+                Value::Map(ref map) => {
+                    for (name, schema) in fields {
+                        with_segment(path, name, |path| {
+                            match map.get(&Value::String(name.clone())) {
+                                Some(v) => v.validate_at(schema, path, errors),
+                                None => match *schema {
+                                    Schema::Option(_) => {}
+                                    _ => errors.push(ValidationError::new(path, "missing required field".to_owned())),
+                                },
+                            }
+                        });
+                    }
+
+                    true
+                }
+                _ => false,
+            },
+        };
+
+        if !matched {
+            let message = format!("expected {}, found {}", schema.description(), self.description());
+            errors.push(ValidationError::new(path, message));
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match *self {
+            Value::Bool(_) => "a boolean",
+            Value::Char(_) => "a char",
+            Value::Map(_) => "a map",
+            Value::Number(_) => "a number",
+            Value::Option(_) => "an option",
+            Value::String(_) => "a string",
+            Value::Seq(_) => "a sequence",
+            Value::Unit => "a unit value",
+        }
+    }
+}
+
+/// Converts a map key to a string for use in paths and JSON objects,
+/// writing keys which are not strings as RON.
+fn key_to_string(key: &Value) -> String {
+    match *key {
+        Value::String(ref s) => s.clone(),
+        ref k => ::ser::to_string(k).expect("Bug: Value serialization failed"),
+    }
+}
+
+fn with_segment<F, R>(path: &mut String, segment: &str, f: F) -> R
+    where F: FnOnce(&mut String) -> R
+{
+    let len = path.len();
+    path.push('/');
+    path.push_str(segment);
+    let result = f(path);
+    path.truncate(len);
+
+    result
+}
+
+fn diff_entry(a: Option<&Value>, b: Option<&Value>, path: &mut String, f: &mut fmt::Formatter) -> fmt::Result {
+    if let (Some(a), Some(b)) = (a, b) {
+        return a.diff_at(b, path, f);
+    }
+
+    write_change(a, b, path, f)
+}
+
+fn write_change(a: Option<&Value>, b: Option<&Value>, path: &str, f: &mut fmt::Formatter) -> fmt::Result {
+    let ron = |v| ::ser::to_string(v).expect("Bug: Value serialization failed");
+
+    writeln!(f, "{}", if path.is_empty() { "/" } else { path })?;
+    if let Some(a) = a {
+        writeln!(f, "- {}", ron(a))?;
+    }
+    if let Some(b) = b {
+        writeln!(f, "+ {}", ron(b))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the differences between `a` and `b` as written by
+/// `Value::format_diff`, or an empty string if they are equal.
+pub fn value_diff_string(a: &Value, b: &Value) -> String {
+    struct Diff<'a>(&'a Value, &'a Value);
+
+    impl<'a> fmt::Display for Diff<'a> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            Value::format_diff(self.0, self.1, f)
+        }
+    }
+
+    Diff(a, b).to_string()
+}
+
+/// A description of the expected shape of a `Value`, used by `Value::validate`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schema {
+    /// Accepts any value.
+    Any,
+    Bool,
+    Char,
+    /// A number, optionally restricted to the inclusive range `min..=max`.
+    Number { min: Option<f64>, max: Option<f64> },
+    String,
+    Unit,
+    /// `None` or `Some` with a value matching the inner schema.
+    Option(Box<Schema>),
+    /// A sequence whose elements all match the inner schema.
+    Seq(Box<Schema>),
+    /// A map whose keys and values match the respective schemas.
+    Map(Box<Schema>, Box<Schema>),
+    /// A struct with the given fields.
+    ///
+    /// All fields are required, except for those with an `Option` schema.
+    /// Unknown fields are ignored, just like serde does by default.
+    Struct(BTreeMap<String, Schema>),
+}
+
+impl Schema {
+    fn description(&self) -> &'static str {
+        match *self {
+            Schema::Any => "any value",
+            Schema::Bool => "a boolean",
+            Schema::Char => "a char",
+            Schema::Number { .. } => "a number",
+            Schema::String => "a string",
+            Schema::Unit => "a unit value",
+            Schema::Option(_) => "an option",
+            Schema::Seq(_) => "a sequence",
+            Schema::Map(..) => "a map",
+            Schema::Struct(_) => "a struct",
+        }
+    }
+}
+
+/// An error of `Value::unflatten`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnflattenError {
+    /// The path which is both a leaf and the prefix of another path
+    pub key: String,
+}
+
+impl fmt::Display for UnflattenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` is both a value and the prefix of another key", self.key)
+    }
+}
+
+impl ::std::error::Error for UnflattenError {}
+
+/// A node of the tree `Value::unflatten` builds.
+enum FlatNode {
+    Leaf(Value),
+    Branch(BTreeMap<String, FlatNode>),
+}
+
+impl FlatNode {
+    fn into_value(self) -> Value {
+        let children = match self {
+            FlatNode::Leaf(v) => return v,
+            FlatNode::Branch(children) => children,
+        };
+
+        // Distinct indices below the number of children are exactly 0..n
+        let is_seq = !children.is_empty() && children.keys().all(|k| {
+            k.parse::<usize>().is_ok_and(|i| i < children.len() && i.to_string() == *k)
+        });
+
+        if is_seq {
+            let mut elements: Vec<_> = children
+                .into_iter()
+                .map(|(k, v)| (k.parse::<usize>().unwrap(), v))
+                .collect();
+            elements.sort_unstable_by_key(|&(i, _)| i);
+
+            Value::Seq(elements.into_iter().map(|(_, v)| v.into_value()).collect())
+        } else {
+            Value::Map(children
+                .into_iter()
+                .map(|(k, v)| (Value::String(k), v.into_value()))
+                .collect())
+        }
+    }
+}
+
+/// A single violation found by `Value::validate`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidationError {
+    /// The location of the offending value, like `/server/port`.
+    ///
+    /// The root value has an empty path.
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    fn new(path: &str, message: String) -> Self {
+        ValidationError {
+            path: path.to_owned(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Converts a RON value into a JSON value.
+///
+/// The conversion is lossy in a few places:
+///
+/// * struct names are not part of a `Value` in the first place
+/// * chars become one-char strings
+/// * `()` and `None` both become `null`, and `Some(v)` becomes just `v`
+/// * map keys which are not strings are replaced by their RON representation
+/// * integral numbers become JSON integers, all others JSON floats
+/// * map entries are sorted by key, unless serde_json preserves the order
+#[cfg(feature = "json")]
+pub fn to_json_value(ron: &Value) -> ::serde_json::Value {
+    use serde_json::Value as Json;
+
+    match *ron {
+        Value::Bool(b) => Json::Bool(b),
+        Value::Char(c) => Json::String(c.to_string()),
+        Value::Map(ref m) => Json::Object(m.iter()
+            .map(|(k, v)| (key_to_string(k), to_json_value(v)))
+            .collect()),
+        Value::Number(n) => {
+            let f = n.get();
+
+            if f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64 {
+                Json::Number((f as i64).into())
+            } else {
+                ::serde_json::Number::from_f64(f).map(Json::Number).unwrap_or(Json::Null)
+            }
+        }
+        Value::Option(Some(ref v)) => to_json_value(v),
+        Value::Option(None) | Value::Unit => Json::Null,
+        Value::String(ref s) => Json::String(s.clone()),
+        Value::Seq(ref s) => Json::Array(s.iter().map(to_json_value).collect()),
+    }
+}
+
+/// Converts a JSON value into a RON value.
+///
+/// `null` becomes `()`, all numbers are converted to `f64`
+/// and objects become maps with string keys.
+#[cfg(feature = "json")]
+pub fn from_json_value(json: &::serde_json::Value) -> Value {
+    use serde_json::Value as Json;
+
+    match *json {
+        Json::Null => Value::Unit,
+        Json::Bool(b) => Value::Bool(b),
+        Json::Number(ref n) => Value::Number(Number::new(n.as_f64().unwrap_or(0.0))),
+        Json::String(ref s) => Value::String(s.clone()),
+        Json::Array(ref a) => Value::Seq(a.iter().map(from_json_value).collect()),
+        Json::Object(ref o) => Value::Map(o.iter()
+            .map(|(k, v)| (Value::String(k.clone()), from_json_value(v)))
+            .collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(s: &str) -> Value {
+        Value::from_str(s).expect("Failed to parse")
+    }
+
+    fn lowercase(v: Value) -> Value {
+        match v {
+            Value::String(s) => Value::String(s.to_lowercase()),
+            v => v,
+        }
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_owned())
+    }
+
+    #[test]
+    fn test_type_name() {
+        for &(s, name) in &[
+            ("true", "bool"), ("'c'", "char"), ("{1: 2}", "map"), ("(a: 1)", "map"),
+            ("42", "integer"), ("-1e3", "integer"), ("4.5", "float"), ("None", "option"),
+            ("Some(1)", "option"), ("\"s\"", "string"), ("[]", "seq"), ("()", "unit"),
+        ] {
+            assert_eq!(eval(s).type_name(), name, "{}", s);
+        }
+
+        assert_eq!(Value::Number(Number::new(f64::NAN)).type_name(), "float");
+        assert_eq!(Value::Number(Number::new(f64::INFINITY)).type_name(), "float");
+    }
+
+    #[test]
+    fn test_len() {
+        for &(s, len) in &[
+            ("[]", 0), ("[1]", 1), ("[1, [], 3]", 3),
+            ("{}", 0), ("{1: 2}", 1), ("{1: 2, 3: 4}", 2),
+            ("(a: 1)", 1), ("(a: 1, b: 2)", 2),
+        ] {
+            let v = eval(s);
+            assert_eq!(v.len(), Some(len), "{}", s);
+            assert_eq!(v.is_empty(), len == 0, "{}", s);
+        }
+
+        for s in &["()", "true", "'c'", "1.5", "\"\"", "None", "Some([])"] {
+            let v = eval(s);
+            assert_eq!(v.len(), None, "{}", s);
+            assert!(!v.is_empty(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_keys_values() {
+        let v = eval("Point(x: 1, y: 2, label: \"origin\")");
+        assert_eq!(v.keys().unwrap().collect::<Vec<_>>(), vec![&string("x"), &string("y"), &string("label")]);
+        assert_eq!(v.values().unwrap().collect::<Vec<_>>(),
+                   vec![&Value::Number(Number::new(1.0)), &Value::Number(Number::new(2.0)), &string("origin")]);
+
+        let v = eval("{}");
+        assert_eq!(v.keys().map(Iterator::count), Some(0));
+        assert_eq!(v.values().map(Iterator::count), Some(0));
+
+        assert!(eval("[1, 2]").keys().is_none());
+        assert!(eval("Some({1: 2})").values().is_none());
+    }
+
+    #[test]
+    fn test_contains() {
+        let v = eval("(name: \"ron\", tags: [1, 2], \"quoted\": ())");
+        assert!(v.contains_key("name"));
+        assert!(v.contains_key("quoted"));
+        assert!(!v.contains_key("missing"));
+        assert!(!v.contains_index(0));
+
+        let v = eval("{\"a\": 1, 'c': 2, 0: 3}");
+        assert!(v.contains_key("a"));
+        assert!(!v.contains_key("c"));
+        assert!(!v.contains_key("0"));
+
+        let v = eval("[1, 2]");
+        assert!(v.contains_index(0));
+        assert!(v.contains_index(1));
+        assert!(!v.contains_index(2));
+        assert!(!v.contains_key("0"));
+
+        assert!(!eval("\"name\"").contains_key("name"));
+        assert!(!eval("Some([1])").contains_index(0));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut v = eval("(a: 1, b: [true, false], c: ())");
+        assert_eq!(v.remove("b"), Some(eval("[true, false]")));
+        assert_eq!(v.remove("b"), None);
+        assert_eq!(v.remove("d"), None);
+        assert_eq!(v.remove_index(0), None);
+        assert_eq!(v, eval("(a: 1, c: ())"));
+
+        let mut v = eval("['a', 'b', 'c']");
+        assert_eq!(v.remove_index(1), Some(Value::Char('b')));
+        assert_eq!(v.remove_index(2), None);
+        assert_eq!(v.remove("a"), None);
+        assert_eq!(v, eval("['a', 'c']"));
+
+        let mut v = eval("Some((a: 1))");
+        assert_eq!(v.remove("a"), None);
+        assert_eq!(v.remove_index(0), None);
+        assert_eq!(v, eval("Some((a: 1))"));
+    }
+
+    #[test]
+    fn test_take() {
+        let mut config = eval("(
+            logging: (level: \"debug\", targets: [\"stderr\", \"file\"]),
+            server: Some((port: 8080, \"a.b\": 1, \"c/d~\": 2)),
+        )");
+
+        assert_eq!(config.take("logging.targets.1"), Some(string("file")));
+        assert_eq!(config.take("logging.targets.1"), None);
+        assert_eq!(config.take("logging"), Some(eval("(level: \"debug\", targets: [\"stderr\"])")));
+        assert_eq!(config.take("logging"), None);
+        assert_eq!(config.take("server.port"), Some(Value::Number(Number::new(8080.0))));
+        assert_eq!(config.take("server.a.b"), None);
+        assert_eq!(config, eval("(server: Some((\"a.b\": 1, \"c/d~\": 2)))"));
+
+        assert_eq!(config.take_pointer("/server/a.b"), Some(Value::Number(Number::new(1.0))));
+        assert_eq!(config.take_pointer("/server/c~1d~0"), Some(Value::Number(Number::new(2.0))));
+        assert_eq!(config.take_pointer("/server/port"), None);
+        assert_eq!(config.take("server"), Some(Value::Option(Some(Box::new(Value::Map(Map::new()))))));
+        assert!(config.is_empty());
+
+        let mut v = eval("[[1, 2], 3]");
+        assert_eq!(v.take(""), None);
+        assert_eq!(v.take_pointer(""), None);
+        assert_eq!(v.take_pointer("0/0"), None);
+        assert_eq!(v.take("0.x"), None);
+        assert_eq!(v.take("2"), None);
+        assert_eq!(v.take("0.0.0"), None);
+        assert_eq!(v.take_pointer("/0/0"), Some(Value::Number(Number::new(1.0))));
+        assert_eq!(v, eval("[[2], 3]"));
+    }
+
+    #[test]
+    fn test_flatten() {
+        let v = eval("Config(
+            server: (address: (host: \"localhost\", port: 8080), tls: None),
+            items: ['a', ['b']],
+            empty: [],
+            extra: {1: true, \"key\": {}},
+        )");
+
+        let mut flat: Vec<_> = v.flatten().into_iter().collect();
+        flat.sort();
+        let expected = vec![
+            ("empty", eval("[]")),
+            ("extra.1", eval("true")),
+            ("extra.key", eval("{}")),
+            ("items.0", eval("'a'")),
+            ("items.1.0", eval("'b'")),
+            ("server.address.host", string("localhost")),
+            ("server.address.port", Value::Number(Number::new(8080.0))),
+            ("server.tls", eval("None")),
+        ];
+        let expected: Vec<_> = expected.into_iter().map(|(k, v)| (k.to_owned(), v)).collect();
+        assert_eq!(flat, expected);
+
+        let flat = eval("Some([1])").flatten();
+        assert_eq!(flat.len(), 1);
+        assert_eq!(flat[""], eval("Some([1])"));
+    }
+
+    #[test]
+    fn test_unflatten() {
+        for s in &[
+            "(items: ['a', ['b', 'c']], server: (address: (host: \"localhost\", port: 8080), tls: None))",
+            "(a: [[[], {}], (b: ())], c: Some((e: 1)))",
+            "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]",
+            "true",
+            "[]",
+        ] {
+            let v = eval(s);
+            assert_eq!(Value::unflatten(v.flatten()), Ok(v), "{}", s);
+        }
+
+        let flat = vec![
+            ("server.port", Value::Number(Number::new(8080.0))),
+            ("server.hosts.1", string("b")),
+            ("server.hosts.0", string("a")),
+            ("server.01", Value::Unit),
+            ("log", Value::Bool(true)),
+        ];
+        let flat: HashMap<_, _> = flat.into_iter().map(|(k, v)| (k.to_owned(), v)).collect();
+        let expected = eval("(log: true, server: (\"01\": (), hosts: [\"a\", \"b\"], port: 8080))");
+        assert_eq!(Value::unflatten(flat), Ok(expected));
+
+        assert_eq!(Value::unflatten(HashMap::new()), Ok(Value::Map(Map::new())));
+
+        for &(ref keys, conflict) in &[
+            (["a", "a.b"], "a"),
+            (["x.a.b.c", "x.a.b"], "x.a.b"),
+            (["", "a"], ""),
+        ] {
+            let flat = keys.iter().map(|k| (k.to_string(), Value::Unit)).collect();
+            let err = Value::unflatten(flat).unwrap_err();
+            assert_eq!(err, UnflattenError { key: conflict.to_owned() });
+        }
+
+        assert_eq!(UnflattenError { key: "a.b".to_owned() }.to_string(),
+                   "`a.b` is both a value and the prefix of another key");
+    }
+
+    #[test]
+    fn test_diff() {
+        let a = eval("Server(name: \"main\", port: 8080, tags: [\"a\", \"b\"], tls: Some((cert: \"x\")))");
+        let b = eval("Server(name: \"main\", port: 8081, tags: [\"a\"], tls: Some((cert: \"y\", key: 'k')))");
+
+        assert_eq!(value_diff_string(&a, &b), "\
+/port
+- 8080
++ 8081
+/tags/1
+- \"b\"
+/tls/cert
+- \"x\"
++ \"y\"
+/tls/key
++ 'k'
+");
+
+        assert_eq!(value_diff_string(&a, &a.clone()), "");
+        assert_eq!(value_diff_string(&eval("{1: 2, 3: 4}"), &eval("{3: 4, 1: 2}")), "");
+        assert_eq!(value_diff_string(&eval("[1]"), &eval("Some([1])")), "/\n- [1,]\n+ Some([1,])\n");
+        assert_eq!(value_diff_string(&eval("{'c': (a: [1])}"), &eval("{'c': (a: {})}")),
+                   "/'c'/a\n- [1,]\n+ {}\n");
+    }
+
+    #[test]
+    fn test_insert_push() {
+        let mut tags = Value::Seq(vec![]);
+        tags.push(string("fast"));
+        tags.push(string("small"));
+
+        let mut v = Value::Map(Map::new());
+        assert_eq!(v.insert(string("name"), string("ron")), None);
+        assert_eq!(v.insert(string("tags"), tags), None);
+        assert_eq!(v.insert(string("name"), string("RON")), Some(string("ron")));
+
+        assert_eq!(::ser::to_string(&v).unwrap(), "{\"name\":\"RON\",\"tags\":[\"fast\",\"small\",],}");
+        assert_eq!(v, eval("(name: \"RON\", tags: [\"fast\", \"small\"])"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_not_map() {
+        Value::Seq(vec![]).insert(Value::Unit, Value::Unit);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_push_not_seq() {
+        Value::Map(Map::new()).push(Value::Unit);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let n = Number::new;
+
+        assert_eq!(n(3.0) + n(4.0), Ok(n(7.0)));
+        assert_eq!(n(3.0) + n(0.5), Ok(n(3.5)));
+        assert_eq!(n(0.25) + n(0.5), Ok(n(0.75)));
+        assert_eq!(n(3.0) - n(4.0), Ok(n(-1.0)));
+        assert_eq!(n(3.0) - n(0.5), Ok(n(2.5)));
+        assert_eq!(n(0.75) - n(0.5), Ok(n(0.25)));
+        assert_eq!(n(3.0) * n(-4.0), Ok(n(-12.0)));
+        assert_eq!(n(3.0) * n(0.5), Ok(n(1.5)));
+        assert_eq!(n(0.5) * n(0.5), Ok(n(0.25)));
+        assert_eq!(n(12.0) / n(4.0), Ok(n(3.0)));
+        assert_eq!(n(3.0) / n(0.5), Ok(n(6.0)));
+        assert_eq!(n(0.75) / n(0.25), Ok(n(3.0)));
+        assert_eq!(n(1.0) / n(4.0), Ok(n(0.25)));
+
+        assert_eq!(n(1.0) / n(0.0), Err(ArithmeticError::DivisionByZero));
+        assert_eq!(n(0.0) / n(-0.0), Err(ArithmeticError::DivisionByZero));
+        assert_eq!(n(f64::MAX) + n(f64::MAX), Err(ArithmeticError::Overflow));
+        assert_eq!(n(-f64::MAX) - n(f64::MAX), Err(ArithmeticError::Overflow));
+        assert_eq!(n(f64::MAX) * n(2.0), Err(ArithmeticError::Overflow));
+        assert_eq!(n(f64::MAX) / n(0.5), Err(ArithmeticError::Overflow));
+
+        assert_eq!(n(f64::INFINITY) + n(1.0), Ok(n(f64::INFINITY)));
+        assert!((n(f64::INFINITY) - n(f64::INFINITY)).unwrap().is_nan());
+        assert!((n(f64::NAN) * n(2.0)).unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_map_constructors() {
+        let pairs = vec![(string("b"), Value::Unit), (Value::Char('a'), Value::Bool(true))];
+        let map = Map::from_ordered_pairs(pairs.clone());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), pairs);
+
+        let map = Map::from_str_keys(vec![("z", Value::Unit), ("y", Value::Bool(false)), ("z", Value::Char('z'))]);
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec![&string("z"), &string("y")]);
+        assert_eq!(map.get(&string("z")), Some(&Value::Char('z')));
+        assert_eq!(map.get(&string("x")), None);
+
+        let map = Map::with_capacity(8);
+        assert!(map.is_empty());
+        assert_eq!(map, Map::new());
+    }
+
+    #[test]
+    fn test_map_editing() {
+        let mut map = Map::from_str_keys(vec![("a", Value::Unit), ("b", Value::Unit), ("c", Value::Unit)]);
+
+        assert_eq!(map.insert(string("a"), Value::Bool(true)), Some(Value::Unit));
+        assert_eq!(map.insert(string("d"), Value::Bool(false)), None);
+        assert_eq!(map.remove(&string("b")), Some(Value::Unit));
+        assert_eq!(map.remove(&string("b")), None);
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec![&string("a"), &string("c"), &string("d")]);
+        assert_eq!(map.get(&string("d")), Some(&Value::Bool(false)));
+        assert!(map.contains_key(&string("c")));
+
+        *map.entry(string("c")).or_insert(Value::Bool(false)) = Value::Char('c');
+        map.entry(string("e")).or_insert(Value::Seq(vec![]));
+        if let Some(&mut Value::Seq(ref mut seq)) = map.get_mut(&string("e")) {
+            seq.push(Value::Unit);
+        }
+
+        assert_eq!(map, Map::from_str_keys(vec![
+            ("a", Value::Bool(true)),
+            ("c", Value::Char('c')),
+            ("d", Value::Bool(false)),
+            ("e", Value::Seq(vec![Value::Unit])),
+        ]));
+        let reversed: Map = map.iter().rev().map(|(k, v)| (k.clone(), v.clone())).collect();
+        assert_eq!(map, reversed);
+        assert_ne!(map.keys().collect::<Vec<_>>(), reversed.keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_map_order() {
+        let value = eval("{\"b\": 1, \"a\": 2, 'c': 3}");
+        let map = match value {
+            Value::Map(ref map) => map,
+            _ => panic!("Expected a map"),
+        };
+
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec![&string("b"), &string("a"), &Value::Char('c')]);
+        assert_eq!(format!("{:?}", map), "{String(\"b\"): Number(Number(1.0)), \
+                                          String(\"a\"): Number(Number(2.0)), \
+                                          Char('c'): Number(Number(3.0))}");
+        assert_eq!(::ser::to_string(&value).unwrap(), "{\"b\":1,\"a\":2,'c':3,}");
+    }
+
+    #[test]
+    fn test_number_ordering() {
+        let mut numbers: Vec<_> = [f64::NAN, 1.5, -1.0, f64::INFINITY, 0.0, f64::NEG_INFINITY, -0.5]
+            .iter()
+            .map(|&f| Number::new(f))
+            .collect();
+        numbers.sort();
+
+        let sorted: Vec<_> = numbers.iter().map(Number::get).collect();
+        assert_eq!(&sorted[..6], &[f64::NEG_INFINITY, -1.0, -0.5, 0.0, 1.5, f64::INFINITY]);
+        assert!(sorted[6].is_nan());
+
+        assert_eq!(Number::new(f64::NAN), Number::new(f64::NAN));
+        assert!(Number::new(f64::NAN) > Number::new(f64::INFINITY));
+        assert_eq!(Number::new(0.0), Number::new(-0.0));
+    }
+
+    #[test]
+    fn test_number_classification() {
+        assert!(Number::new(f64::NAN).is_nan());
+        assert!(!Number::new(f64::NAN).is_finite());
+        assert!(!Number::new(f64::INFINITY).is_finite());
+        assert!(!Number::new(1.0).is_nan());
+        assert!(Number::new(1.0).is_finite());
+    }
+
+    #[test]
+    fn test_huge_number() {
+        assert_eq!(eval("1e999"), Value::Number(Number::new(f64::INFINITY)));
+    }
+
+    fn server_schema() -> Schema {
+        let mut server = BTreeMap::new();
+        server.insert("host".to_owned(), Schema::String);
+        server.insert("port".to_owned(), Schema::Number { min: Some(1.0), max: Some(65535.0) });
+        server.insert("name".to_owned(), Schema::Option(Box::new(Schema::String)));
+
+        let mut root = BTreeMap::new();
+        root.insert("server".to_owned(), Schema::Struct(server));
+        root.insert("users".to_owned(), Schema::Seq(Box::new(Schema::String)));
+
+        Schema::Struct(root)
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = server_schema();
+
+        let valid = eval("(server: (host: \"localhost\", port: 80), users: [\"admin\"])");
+        assert_eq!(valid.validate(&schema), vec![]);
+
+        let invalid = eval("(server: (port: 0, name: Some(1)), users: [\"admin\", 'b'])");
+        assert_eq!(invalid.validate(&schema), vec![
+            ValidationError::new("/server/host", "missing required field".to_owned()),
+            ValidationError::new("/server/name", "expected a string, found a number".to_owned()),
+            ValidationError::new("/server/port", "0 is out of range 1..=65535".to_owned()),
+            ValidationError::new("/users/1", "expected a string, found a char".to_owned()),
+        ]);
+
+        assert_eq!(eval("[]").validate(&schema)[0].to_string(), ": expected a struct, found a sequence");
+    }
+
+    #[test]
+    fn test_map_keys() {
+        let config = eval("Config(
+            Name: \"Server\",
+            Address: (Host: \"LOCALHOST\", Port: 80),
+            Users: [(Name: \"Admin\")],
+            Backup: Some((Path: \"/TMP\")),
+        )");
+
+        assert_eq!(config.map_keys(lowercase), eval("(
+            name: \"Server\",
+            address: (host: \"LOCALHOST\", port: 80),
+            users: [(name: \"Admin\")],
+            backup: Some((path: \"/TMP\")),
+        )"));
+    }
+
+    #[test]
+    fn test_map_values() {
+        let value = eval("(Name: \"Server\", Tags: [\"A\", 'B'], Extra: Some(\"X\"))");
+
+        assert_eq!(value.map_values(lowercase),
+                   eval("(Name: \"server\", Tags: [\"a\", 'B'], Extra: Some(\"x\"))"));
+    }
+
+    #[test]
+    fn test_map_all() {
+        use std::cell::Cell;
+
+        let value = eval("{\"A\": [\"B\"], \"C\": Some(\"D\")}");
+        assert_eq!(value.clone().map_all(lowercase),
+                   eval("{\"a\": [\"b\"], \"c\": Some(\"d\")}"));
+
+        // 2 keys, 2 leaves, 1 seq, 1 option and the map itself
+        let count = Cell::new(0);
+        value.map_all(|v| { count.set(count.get() + 1); v });
+        assert_eq!(count.get(), 7);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_to_json() {
+        let ron = eval("Scene(
+            name: \"scene\",
+            size: (x: 1.5, y: -2),
+            tags: ['a', 'b'],
+            parent: None,
+            child: Some(()),
+            layers: {
+                1: true,
+            },
+        )");
+
+        assert_eq!(to_json_value(&ron), json!({
+            "name": "scene",
+            "size": { "x": 1.5, "y": -2 },
+            "tags": ["a", "b"],
+            "parent": null,
+            "child": null,
+            "layers": { "1": true },
+        }));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_json_roundtrip() {
+        let json = json!({
+            "name": "scene",
+            "size": { "x": 1.5, "y": -2 },
+            "tags": ["a", "b"],
+            "parent": null,
+            "visible": false,
+        });
+
+        assert_eq!(to_json_value(&from_json_value(&json)), json);
+
+        // JSON objects are sorted by key
+        let ron = eval("(name: \"scene\", parent: (), size: (x: 1.5, y: -2), tags: [\"a\"])");
+        assert_eq!(from_json_value(&to_json_value(&ron)), ron);
+    }
+}
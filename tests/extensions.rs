@@ -0,0 +1,191 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use ron::extensions::Extensions;
+use ron::ser::{PrettyConfig, to_string_pretty};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Meters(f64);
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Name(String);
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Wrapper(Point);
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Config {
+    length: Meters,
+    name: Name,
+    origin: Wrapper,
+}
+
+#[test]
+fn unwrap_newtypes() {
+    let config = Config {
+        length: Meters(4.2),
+        name: Name("ruler".to_string()),
+        origin: Wrapper(Point { x: 1, y: -2 }),
+    };
+
+    let pretty = PrettyConfig::basic(false).with_extensions(Extensions::UNWRAP_NEWTYPES);
+    let s = to_string_pretty(&config, pretty).unwrap();
+
+    assert_eq!(s, "#![enable(unwrap_newtypes)](length:4.2,name:\"ruler\",origin:(x:1,y:-2,),)");
+    assert_eq!(ron::de::from_str(&s), Ok(config));
+}
+
+#[test]
+fn unwrap_newtypes_disabled() {
+    assert!(ron::de::from_str::<Meters>("4.2").is_err());
+    assert_eq!(ron::de::from_str("#![enable(unwrap_newtypes)] 4.2"), Ok(Meters(4.2)));
+    assert_eq!(ron::de::from_str("// comment\n# ! [ enable ( unwrap_newtypes , ) ]\n4.2"),
+               Ok(Meters(4.2)));
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Lenient {
+    length: Meters,
+    origin: Wrapper,
+    shape: Shape,
+    label: Option<Option<String>>,
+}
+
+#[test]
+fn lenient() {
+    let s = "(length: 4.2, origin: (x: 1, y: 2), shape: Tuple(3,), label: \"a\")";
+    let expected = Lenient {
+        length: Meters(4.2),
+        origin: Wrapper(Point { x: 1, y: 2 }),
+        shape: Shape::Tuple((3,)),
+        label: Some(Some("a".to_owned())),
+    };
+
+    assert!(ron::de::from_str::<Lenient>(s).is_err());
+    assert_eq!(ron::de::from_str_lenient(s), Ok(expected));
+
+    assert_eq!(ron::de::from_str_lenient("Some(None)"), Ok(Some(None::<i32>)));
+}
+
+#[test]
+fn unknown_extension() {
+    assert!(ron::de::from_str::<Meters>("#![enable(no_such_extension)] 4.2").is_err());
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+enum Shape {
+    Tuple((i32,)),
+    Pair((i32, i32)),
+    Point(Point),
+    Length(Meters),
+    Optional(Option<Point>),
+    Nested(Box<Shape>),
+}
+
+#[test]
+fn unwrap_variant_newtypes() {
+    let shapes = vec![
+        Shape::Tuple((1,)),
+        Shape::Pair((2, 3)),
+        Shape::Point(Point { x: 4, y: 5 }),
+        Shape::Length(Meters(6.5)),
+        Shape::Optional(Some(Point { x: 7, y: 8 })),
+        Shape::Nested(Box::new(Shape::Point(Point { x: 9, y: 10 }))),
+    ];
+
+    let pretty = PrettyConfig::basic(true).with_extensions(Extensions::UNWRAP_VARIANT_NEWTYPES);
+    let s = to_string_pretty(&shapes, pretty).unwrap();
+
+    assert_eq!(s, "#![enable(unwrap_variant_newtypes)][Tuple(1,),Pair(2,3,),Point(x:4,y:5,),Length(6.5),\
+                   Optional(Some(Point(x:7,y:8,))),Nested(Point(x:9,y:10,)),]");
+    assert_eq!(ron::de::from_str(&s), Ok(shapes));
+
+    assert!(ron::de::from_str::<Shape>("Point(x: 4, y: 5)").is_err());
+    assert_eq!(ron::de::from_str("Point((x: 4, y: 5))"), Ok(Shape::Point(Point { x: 4, y: 5 })));
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Scene {
+    name: Option<Name>,
+    shapes: Vec<Shape>,
+    scale: Option<Meters>,
+    pair: Option<(Option<Point>, bool)>,
+}
+
+#[test]
+fn pretty_with_all_extensions() {
+    let scene = Scene {
+        name: Some(Name("scene".to_string())),
+        shapes: vec![
+            Shape::Point(Point { x: 1, y: 2 }),
+            Shape::Optional(None),
+            Shape::Pair((3, 4)),
+        ],
+        scale: None,
+        pair: Some((Some(Point { x: 5, y: 6 }), true)),
+    };
+
+    let mut pretty = PrettyConfig::default().with_extensions(Extensions::all());
+    pretty.new_line = "\n".to_string();
+    pretty.separate_tuple_members = true;
+    let s = to_string_pretty(&scene, pretty).unwrap();
+
+    assert_eq!(s, "\
+#![enable(implicit_some)]
+#![enable(unwrap_newtypes)]
+#![enable(unwrap_variant_newtypes)]
+#![enable(bytes_as_base64)]
+#![enable(bytes_as_hex)]
+Scene(
+    name: \"scene\",
+    shapes: [
+        Point(
+            x: 1,
+            y: 2,
+        ),
+        Optional(None),
+        Pair(
+            3,
+            4,
+        ),
+    ],
+    scale: None,
+    pair: (
+        Point(
+            x: 5,
+            y: 6,
+        ),
+        true,
+    ),
+)");
+    assert_eq!(ron::de::from_str(&s), Ok(scene));
+}
+
+#[test]
+fn extensions_roundtrip() {
+    use ron::de::DeserializerConfig;
+
+    let extensions = Extensions::IMPLICIT_SOME | Extensions::UNWRAP_NEWTYPES;
+    let s = ron::ser::to_string(&extensions).unwrap();
+    assert_eq!(s, "[\"implicit_some\",\"unwrap_newtypes\",]");
+    assert_eq!(ron::de::from_str(&s), Ok(extensions));
+    assert_eq!(ron::de::from_str("[]"), Ok(Extensions::empty()));
+    assert!(ron::de::from_str::<Extensions>("[\"implicit_none\"]").is_err());
+
+    let config = DeserializerConfig { extensions: Extensions::all(), ..Default::default() };
+    let s = ron::ser::to_string(&config).unwrap();
+    assert_eq!(ron::de::from_str(&s), Ok(config));
+    assert_eq!(ron::de::from_str("()"), Ok(DeserializerConfig::default()));
+
+    let pretty = PrettyConfig::default().with_extensions(Extensions::UNWRAP_NEWTYPES);
+    let s = ron::ser::to_string(&pretty).unwrap();
+    let pretty: PrettyConfig = ron::de::from_str(&s).unwrap();
+    assert_eq!(pretty.extensions, Extensions::UNWRAP_NEWTYPES);
+}
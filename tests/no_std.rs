@@ -0,0 +1,23 @@
+#![no_std]
+
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use ron::ser::to_string_fixed;
+
+#[derive(Serialize)]
+struct Point {
+    x: i32,
+    y: i32,
+    label: &'static str,
+}
+
+#[test]
+fn serialize_struct() {
+    let mut buf = [0; 32];
+    let len = to_string_fixed(&Point { x: 1, y: -2, label: "a" }, &mut buf).unwrap();
+
+    assert_eq!(&buf[..len], b"(x:1,y:-2,label:\"a\",)");
+    assert!(to_string_fixed(&Point { x: 1, y: -2, label: "a" }, &mut [0; 8]).is_err());
+}
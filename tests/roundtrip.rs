@@ -51,3 +51,35 @@ fn roundtrip() {
 
     assert_eq!(Ok(value), deserial);
 }
+
+#[test]
+fn error_equality() {
+    use ron::de::{Error, ParseError, Position};
+
+    assert_eq!(ron::de::from_str::<u8>("x"),
+               Err(Error::Parser(ParseError::ExpectedInteger, Position { line: 1, col: 1 })));
+    assert_eq!(ron::de::from_str::<Struct>("(tuple: 5)"),
+               Err(Error::Parser(ParseError::ExpectedArray, Position { line: 1, col: 9 })));
+    assert_eq!(ron::ser::to_string(&Struct {
+        tuple: ((), NewType(0.5), TupleStruct(UnitStruct, 0)),
+        vec: vec![],
+        map: HashMap::new(),
+    }), Ok("(tuple:((),(0.5),((),0,),),vec:[],map:{},)".to_string()));
+}
+
+#[test]
+fn human_readable() {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    // Addresses are written as strings by human-readable formats
+    // and as tuples of numbers by others
+    let ip = Ipv4Addr::new(127, 0, 0, 1);
+    let s = ron::ser::to_string(&ip).unwrap();
+    assert_eq!(s, "\"127.0.0.1\"");
+    assert_eq!(ron::de::from_str(&s), Ok(ip));
+
+    let addr = SocketAddr::new(IpAddr::V4(ip), 8080);
+    let s = ron::ser::to_string(&addr).unwrap();
+    assert_eq!(s, "\"127.0.0.1:8080\"");
+    assert_eq!(ron::de::from_str(&s), Ok(addr));
+}
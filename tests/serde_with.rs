@@ -0,0 +1,79 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+extern crate serde_with;
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde_with::{serde_as, DisplayFromStr};
+
+/// A URL, only convertible from and to strings, like the one of the `url` crate.
+#[derive(Debug, PartialEq)]
+struct Url {
+    scheme: String,
+    host: String,
+}
+
+impl FromStr for Url {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut parts = s.splitn(2, "://");
+        match (parts.next(), parts.next()) {
+            (Some(scheme), Some(host)) => Ok(Url { scheme: scheme.to_string(), host: host.to_string() }),
+            _ => Err(format!("invalid url `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}://{}", self.scheme, self.host)
+    }
+}
+
+#[serde_as]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Link {
+    #[serde_as(as = "DisplayFromStr")]
+    url: Url,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    mirror: Option<Url>,
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    ports: Vec<u16>,
+}
+
+#[serde_as]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Wrapper(#[serde_as(as = "DisplayFromStr")] Url);
+
+fn example() -> Url {
+    Url { scheme: "http".to_string(), host: "example.com".to_string() }
+}
+
+#[test]
+fn display_from_str() {
+    assert_eq!(ron::de::from_str::<Wrapper>("(\"http://example.com\")"), Ok(Wrapper(example())));
+    assert_eq!(ron::de::from_str::<Wrapper>("Wrapper(\"http://example.com\")"), Ok(Wrapper(example())));
+    assert_eq!(
+        ron::de::from_str_with_struct_name_check::<Wrapper>("Wrapper(\"http://example.com\")"),
+        Ok(Wrapper(example()))
+    );
+
+    let link = Link { url: example(), mirror: None, ports: vec![80, 8080] };
+    let s = ron::ser::to_string(&link).unwrap();
+    assert_eq!(s, r#"(url:"http://example.com",mirror:None,ports:["80","8080",],)"#);
+    assert_eq!(ron::de::from_str::<Link>(&s), Ok(link));
+
+    let s = r#"Link(url: "http://example.com", mirror: Some("ftp://example.org"), ports: [])"#;
+    let link = Link {
+        url: example(),
+        mirror: Some(Url { scheme: "ftp".to_string(), host: "example.org".to_string() }),
+        ports: vec![],
+    };
+    assert_eq!(ron::de::from_str::<Link>(s), Ok(link));
+
+    let err = ron::de::from_str::<Wrapper>("(\"example.com\")").unwrap_err();
+    assert_eq!(err, ron::de::Error::Message("invalid url `example.com`".to_string()));
+}
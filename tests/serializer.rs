@@ -0,0 +1,61 @@
+extern crate ron;
+extern crate serde;
+extern crate serde_json;
+
+use serde::Serialize;
+
+use ron::extensions::Extensions;
+use ron::ser::{Commented, PrettyConfig, Serializer};
+
+#[test]
+fn raw_str_by_hand() {
+    let path = r#"C:\Users\"ron""#;
+
+    let mut serializer = Serializer::new(String::new(), PrettyConfig::basic(false)).unwrap();
+    serializer.serialize_str_raw(path).unwrap();
+    let s = serializer.into_inner();
+
+    assert_eq!(s, r###"r#"C:\Users\"ron""#"###);
+    assert_eq!(ron::de::from_str::<String>(&s), Ok(path.to_string()));
+}
+
+#[test]
+fn into_inner() {
+    let config = PrettyConfig::basic(false).with_extensions(Extensions::IMPLICIT_SOME);
+
+    let serializer = Serializer::new(String::new(), config).unwrap();
+    assert_eq!(serializer.into_inner(), "#![enable(implicit_some)]");
+
+    let mut serializer = Serializer::new(String::new(), PrettyConfig::basic(false)).unwrap();
+    Some((1, "a")).serialize(&mut serializer).unwrap();
+    assert_eq!(serializer.into_inner(), "Some((1,\"a\",))");
+}
+
+#[derive(Serialize)]
+struct Server {
+    host: Commented<&'static str>,
+    ports: Vec<Commented<u16>>,
+}
+
+#[test]
+fn commented() {
+    let server = Server {
+        host: Commented("localhost", "where to listen"),
+        ports: vec![Commented(80, "http"), Commented(443, "https\nneeds a certificate")],
+    };
+
+    assert_eq!(ron::ser::to_string_pretty(&server, PrettyConfig::default()).unwrap(), "\
+Server(
+    // where to listen
+    host: \"localhost\",
+    ports: [
+        // http
+        80,
+        // https
+        // needs a certificate
+        443,
+    ],
+)");
+    assert_eq!(ron::ser::to_string(&server).unwrap(), "(host:\"localhost\",ports:[80,443,],)");
+    assert_eq!(serde_json::to_string(&server).unwrap(), r#"{"host":"localhost","ports":[80,443]}"#);
+}
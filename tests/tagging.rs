@@ -0,0 +1,105 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use ron::de::{from_str, Error};
+use ron::ser::to_string;
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+enum External {
+    Unit,
+    Circle { r: f32 },
+    Pair(u8, u8),
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type")]
+enum Internal {
+    Unit,
+    Circle { r: f32 },
+    Point(Point),
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "t", content = "c")]
+enum Adjacent {
+    Unit,
+    Circle { r: f32 },
+    Pair(u8, u8),
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+enum Untagged {
+    Circle { r: f32 },
+    Pair(u8, u8),
+    Name(String),
+    Unit,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn roundtrip<T>(value: T, expected: &str)
+    where T: ::std::fmt::Debug + PartialEq + serde::Serialize + for<'de> serde::Deserialize<'de>
+{
+    let s = to_string(&value).unwrap();
+    assert_eq!(s, expected);
+    assert_eq!(from_str::<T>(&s), Ok(value));
+}
+
+#[test]
+fn externally_tagged() {
+    roundtrip(External::Unit, "Unit");
+    roundtrip(External::Circle { r: 1.5 }, "Circle(r:1.5,)");
+    roundtrip(External::Pair(1, 2), "Pair(1,2,)");
+
+    assert_eq!(from_str("Circle (r: 1.5)"), Ok(External::Circle { r: 1.5 }));
+}
+
+#[test]
+fn internally_tagged() {
+    roundtrip(Internal::Unit, "(type:\"Unit\",)");
+    roundtrip(Internal::Circle { r: 1.5 }, "(type:\"Circle\",r:1.5,)");
+    roundtrip(Internal::Point(Point { x: 1, y: 2 }), "(type:\"Point\",x:1,y:2,)");
+
+    // The tag may come anywhere and be written as an identifier
+    assert_eq!(from_str("(r: 1.5, type: \"Circle\")"), Ok(Internal::Circle { r: 1.5 }));
+    assert_eq!(from_str("(type: Circle, r: 1.5)"), Ok(Internal::Circle { r: 1.5 }));
+    assert_eq!(from_str("Circle(type: \"Circle\", r: 1.5)"), Ok(Internal::Circle { r: 1.5 }));
+    assert_eq!(
+        from_str::<Internal>("(type: \"Square\")"),
+        Err(Error::Message("unknown variant `Square`, expected one of `Unit`, `Circle`, `Point`".to_string()))
+    );
+}
+
+#[test]
+fn adjacently_tagged() {
+    roundtrip(Adjacent::Unit, "(t:Unit,)");
+    roundtrip(Adjacent::Circle { r: 1.5 }, "(t:Circle,c:(r:1.5,),)");
+    roundtrip(Adjacent::Pair(1, 2), "(t:Pair,c:(1,2,),)");
+
+    assert_eq!(from_str("(c: (r: 1.5), t: Circle)"), Ok(Adjacent::Circle { r: 1.5 }));
+    assert_eq!(from_str("(t: \"Circle\", c: (r: 1.5))"), Ok(Adjacent::Circle { r: 1.5 }));
+    assert_eq!(
+        from_str::<Adjacent>("(t: Square)"),
+        Err(Error::Message("unknown variant `Square`, expected one of `Unit`, `Circle`, `Pair`".to_string()))
+    );
+}
+
+#[test]
+fn untagged() {
+    roundtrip(Untagged::Circle { r: 1.5 }, "(r:1.5,)");
+    roundtrip(Untagged::Pair(1, 2), "(1,2,)");
+    roundtrip(Untagged::Name("x".to_string()), "\"x\"");
+    roundtrip(Untagged::Unit, "()");
+
+    assert_eq!(from_str("Circle(r: 1.5)"), Ok(Untagged::Circle { r: 1.5 }));
+    assert_eq!(
+        from_str::<Untagged>("[1]"),
+        Err(Error::Message("data did not match any variant of untagged enum Untagged".to_string()))
+    );
+}